@@ -0,0 +1,5 @@
+// 埋め込みマイグレーション・シードの変更時に再ビルドさせる
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=seeds");
+}
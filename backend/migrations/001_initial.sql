@@ -55,6 +55,3 @@ CREATE INDEX idx_annotations_image_id ON annotations(image_id);
 CREATE INDEX idx_annotations_user_id ON annotations(user_id);
 CREATE INDEX idx_images_user_id ON images(user_id);
 
--- テストデータ挿入
-INSERT INTO users (cognito_sub, username, email, role) VALUES 
-('test-user-1', 'testuser', 'test@example.com', 'annotator');
@@ -1,4 +1,3 @@
 -- Fix bucket names
-UPDATE images 
-SET s3_bucket = 'kgbacket' 
-WHERE s3_bucket IN ('kg-annotation-bucket', 'test-bucket');
\ No newline at end of file
+-- 環境ごとのバケット名の書き換えはスキーマ履歴から切り離し、scripts/fix_bucket_names.sql に移動しました。
+-- 既存環境の実行履歴を保つため、このバージョン自体は空のまま残しています。
@@ -0,0 +1,3 @@
+-- 無効化されたユーザーは閲覧のみ可能（書き込み系のAPIは 403 を返す）
+ALTER TABLE users
+ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT TRUE;
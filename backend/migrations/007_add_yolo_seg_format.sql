@@ -0,0 +1,2 @@
+-- ポリゴンを書き出す YOLO セグメンテーション形式を追加
+ALTER TYPE dataset_format ADD VALUE IF NOT EXISTS 'yolo-seg' AFTER 'yolo';
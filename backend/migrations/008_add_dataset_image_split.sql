@@ -0,0 +1,6 @@
+-- データセット内での各画像の分割（学習・検証・テスト）
+-- 一度決まった分割は保存し、以降のエクスポートでも同じ分割を使う
+CREATE TYPE dataset_split AS ENUM ('train', 'val', 'test');
+
+ALTER TABLE dataset_images
+ADD COLUMN split dataset_split;
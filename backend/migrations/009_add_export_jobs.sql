@@ -0,0 +1,25 @@
+-- 非同期エクスポートジョブ
+-- ワーカーが pending のジョブを取り出して zip を作成し、オブジェクトストレージに保存する
+CREATE TYPE export_job_status AS ENUM ('pending', 'running', 'completed', 'failed');
+
+CREATE TABLE export_jobs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    dataset_id UUID REFERENCES datasets(id) ON DELETE SET NULL,
+    name VARCHAR NOT NULL,
+    format dataset_format NOT NULL,
+    -- 受け付けたリクエスト（ワーカーが同じ条件でエクスポートする）
+    request JSONB NOT NULL,
+    status export_job_status NOT NULL DEFAULT 'pending',
+    processed_images INTEGER NOT NULL DEFAULT 0,
+    total_images INTEGER,
+    object_key VARCHAR,
+    error TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMP WITH TIME ZONE,
+    finished_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX idx_export_jobs_pending ON export_jobs(created_at) WHERE status = 'pending';
+CREATE INDEX idx_export_jobs_user_id ON export_jobs(user_id, created_at DESC);
@@ -0,0 +1,21 @@
+-- ラベル（クラス）の定義
+-- id はエクスポート時のクラスIDとして使うため、一度振った値は変わらない（削除しても再利用しない）
+CREATE TABLE labels (
+    id INTEGER GENERATED ALWAYS AS IDENTITY (MINVALUE 0 START WITH 0) PRIMARY KEY,
+    name VARCHAR NOT NULL UNIQUE,
+    -- 表示色（#rrggbb）
+    color VARCHAR(7) NOT NULL DEFAULT '#ff0000' CHECK (color ~ '^#[0-9a-fA-F]{6}$'),
+    description TEXT,
+    parent_id INTEGER REFERENCES labels(id) ON DELETE SET NULL,
+    -- アーカイブしたラベルは新しいアノテーションに使えないが、既存のアノテーションはそのまま出力する
+    is_archived BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    CHECK (parent_id IS NULL OR parent_id <> id)
+);
+
+CREATE INDEX idx_labels_parent_id ON labels(parent_id);
+
+-- 既存のアノテーションのラベルを名前順に登録する
+INSERT INTO labels (name)
+SELECT DISTINCT label FROM annotations ORDER BY label;
@@ -0,0 +1,16 @@
+-- ラベルの名前変更・統合の履歴
+CREATE TYPE label_audit_action AS ENUM ('rename', 'merge');
+
+CREATE TABLE label_audit_logs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    action label_audit_action NOT NULL,
+    -- 変更前のラベル名（統合の場合は統合元のすべてのラベル）
+    source_labels TEXT[] NOT NULL,
+    target_label VARCHAR NOT NULL,
+    target_label_id INTEGER REFERENCES labels(id) ON DELETE SET NULL,
+    annotations_changed INTEGER NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_label_audit_logs_created_at ON label_audit_logs(created_at DESC);
@@ -0,0 +1,2 @@
+-- 画像一覧（GET /api/images）のデフォルトの並び順（作成日時, ID）のカーソルページング用
+CREATE INDEX idx_images_created_at_id ON images(created_at, id);
@@ -0,0 +1,12 @@
+-- データセットの状態（locked・published のデータセットに含まれる画像は、force を指定しない限り削除できない）
+CREATE TYPE dataset_status AS ENUM ('draft', 'locked', 'published');
+
+ALTER TABLE datasets
+ADD COLUMN status dataset_status NOT NULL DEFAULT 'draft';
+
+-- 画像の論理削除
+-- deleted_at から復元期間（IMAGE_RESTORE_WINDOW_SECS）が過ぎた画像は、ストレージのオブジェクトとともに完全に削除する
+ALTER TABLE images
+ADD COLUMN deleted_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX idx_images_deleted_at ON images(deleted_at) WHERE deleted_at IS NOT NULL;
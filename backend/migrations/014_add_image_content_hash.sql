@@ -0,0 +1,7 @@
+-- 登録時にサーバー側で計算した画像本体の SHA-256（16進数）
+-- このカラムより前に登録された画像は NULL のまま
+ALTER TABLE images
+ADD COLUMN content_hash VARCHAR(64);
+
+-- 同じオブジェクトを複数の画像として登録できないようにする
+CREATE UNIQUE INDEX idx_images_s3_key ON images(s3_bucket, s3_key);
@@ -0,0 +1,4 @@
+-- 取り込み時に EXIF から読み取ったメタデータ（撮影日時・カメラ・GPS・向き）
+-- width・height は EXIF の向きを反映した（表示される）大きさ
+ALTER TABLE images
+ADD COLUMN metadata JSONB;
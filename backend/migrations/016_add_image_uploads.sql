@@ -0,0 +1,9 @@
+-- 事前署名URL（POST /api/images/presigned-url）で発行したアップロード先のキー
+-- 登録（POST /api/images/register）は発行を受けたユーザーのみ行え、登録すると行を削除する
+CREATE TABLE image_uploads (
+    s3_bucket VARCHAR NOT NULL,
+    s3_key VARCHAR NOT NULL,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (s3_bucket, s3_key)
+);
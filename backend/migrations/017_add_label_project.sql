@@ -0,0 +1,9 @@
+-- ラベルのプロジェクト（NULL はすべてのプロジェクトで共有するラベル）
+-- プロジェクトの表はまだ無いため外部キーは張らない
+ALTER TABLE labels
+ADD COLUMN project_id UUID;
+
+-- 名前はプロジェクトごとに一意（共有ラベルは共有ラベルの中で一意）
+ALTER TABLE labels DROP CONSTRAINT labels_name_key;
+CREATE UNIQUE INDEX idx_labels_project_name ON labels(project_id, name) WHERE project_id IS NOT NULL;
+CREATE UNIQUE INDEX idx_labels_shared_name ON labels(name) WHERE project_id IS NULL;
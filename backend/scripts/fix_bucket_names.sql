@@ -0,0 +1,5 @@
+-- 旧バケット名で登録された画像の s3_bucket を書き換える（環境ごとに手動で実行する）
+-- 例: psql "$DATABASE_URL" -v new_bucket=kgbacket -f scripts/fix_bucket_names.sql
+UPDATE images
+SET s3_bucket = :'new_bucket'
+WHERE s3_bucket IN ('kg-annotation-bucket', 'test-bucket');
@@ -0,0 +1,5 @@
+-- 開発・テスト環境用のユーザー
+-- `kg-annotation-backend migrate seed` で投入する（何度実行しても重複しない）
+INSERT INTO users (cognito_sub, username, email, role) VALUES
+('test-user-1', 'testuser', 'test@example.com', 'annotator')
+ON CONFLICT DO NOTHING;
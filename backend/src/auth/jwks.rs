@@ -0,0 +1,90 @@
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::DecodingKey;
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+
+use super::AuthError;
+
+// 未知の kid を受け取ったときに JWKS を再取得する最短間隔（鍵のローテーション対策）
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+// JWKS の取得元
+#[derive(Debug, Clone)]
+pub enum JwksSource {
+    // オフラインでのテスト用
+    File(PathBuf),
+    Url(String),
+}
+
+struct Cached {
+    keys: JwkSet,
+    fetched_at: Option<Instant>,
+}
+
+// JWT の署名検証に使う公開鍵のキャッシュ
+// 初回の検証時に読み込み、未知の kid が来た場合のみ再取得する
+pub struct Jwks {
+    source: JwksSource,
+    http: reqwest::Client,
+    cached: RwLock<Cached>,
+}
+
+impl Jwks {
+    pub fn new(source: JwksSource) -> Self {
+        Self {
+            source,
+            http: reqwest::Client::new(),
+            cached: RwLock::new(Cached {
+                keys: JwkSet { keys: Vec::new() },
+                fetched_at: None,
+            }),
+        }
+    }
+
+    pub async fn decoding_key(&self, kid: &str) -> Result<DecodingKey, AuthError> {
+        {
+            let cached = self.cached.read().await;
+            if let Some(jwk) = cached.keys.find(kid) {
+                return DecodingKey::from_jwk(jwk).map_err(|e| AuthError::Jwks(e.to_string()));
+            }
+        }
+
+        let mut cached = self.cached.write().await;
+        let stale = cached
+            .fetched_at
+            .is_none_or(|fetched_at| fetched_at.elapsed() >= REFRESH_INTERVAL);
+        // 待機中に他のリクエストが再取得している場合があるため、書き込みロック取得後に再確認する
+        if stale && cached.keys.find(kid).is_none() {
+            cached.keys = self.fetch().await?;
+            cached.fetched_at = Some(Instant::now());
+        }
+
+        let jwk = cached.keys.find(kid).ok_or(AuthError::UnknownKey)?;
+        DecodingKey::from_jwk(jwk).map_err(|e| AuthError::Jwks(e.to_string()))
+    }
+
+    async fn fetch(&self) -> Result<JwkSet, AuthError> {
+        match &self.source {
+            JwksSource::File(path) => {
+                let content = tokio::fs::read_to_string(path)
+                    .await
+                    .map_err(|e| AuthError::Jwks(format!("{}: {}", path.display(), e)))?;
+                serde_json::from_str(&content)
+                    .map_err(|e| AuthError::Jwks(format!("{}: {}", path.display(), e)))
+            }
+            JwksSource::Url(url) => self
+                .http
+                .get(url)
+                .send()
+                .await
+                .and_then(|res| res.error_for_status())
+                .map_err(|e| AuthError::Jwks(format!("{}: {}", url, e)))?
+                .json::<JwkSet>()
+                .await
+                .map_err(|e| AuthError::Jwks(format!("{}: {}", url, e))),
+        }
+    }
+}
@@ -0,0 +1,247 @@
+pub mod jwks;
+pub mod policy;
+
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, StatusCode},
+};
+use jsonwebtoken::{decode, decode_header, Algorithm, Validation};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::fmt;
+
+pub use jwks::{Jwks, JwksSource};
+
+use crate::{
+    config::Config,
+    models::{UserInfo, UserRole},
+    AppState,
+};
+
+// 公開鍵で検証する署名アルゴリズムのみ受け付ける（HS256 などの共有鍵方式は拒否）
+const ALLOWED_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+];
+
+#[derive(Debug)]
+pub enum AuthError {
+    MissingToken,
+    InvalidToken(String),
+    // トークンの kid に対応する公開鍵が JWKS に無い
+    UnknownKey,
+    // JWKS の取得・パースに失敗（サーバー側の問題）
+    Jwks(String),
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthError::MissingToken => write!(f, "missing bearer token"),
+            AuthError::InvalidToken(message) => write!(f, "invalid token: {}", message),
+            AuthError::UnknownKey => write!(f, "token was signed with an unknown key"),
+            AuthError::Jwks(message) => write!(f, "failed to load JWKS: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+impl AuthError {
+    fn status(&self) -> StatusCode {
+        match self {
+            AuthError::Jwks(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
+
+// Cognito が発行する ID トークン・アクセストークンの共通部分
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub email: Option<String>,
+    // IDトークン
+    #[serde(rename = "cognito:username")]
+    pub cognito_username: Option<String>,
+    // アクセストークン
+    pub username: Option<String>,
+    pub aud: Option<serde_json::Value>,
+    pub client_id: Option<String>,
+}
+
+impl Claims {
+    fn username(&self) -> &str {
+        self.cognito_username
+            .as_deref()
+            .or(self.username.as_deref())
+            .unwrap_or(&self.sub)
+    }
+
+    // IDトークンは aud、アクセストークンは client_id にアプリクライアントIDが入る
+    fn has_audience(&self, audience: &str) -> bool {
+        let aud_matches = match &self.aud {
+            Some(serde_json::Value::String(aud)) => aud == audience,
+            Some(serde_json::Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(audience)),
+            _ => false,
+        };
+        aud_matches || self.client_id.as_deref() == Some(audience)
+    }
+}
+
+// Bearer トークンを検証し、users テーブルのユーザーに対応付ける
+pub struct Authenticator {
+    jwks: Jwks,
+    issuer: Option<String>,
+    audience: Option<String>,
+    auto_provision: bool,
+}
+
+impl Authenticator {
+    pub fn from_config(config: &Config) -> Self {
+        let source = match (&config.jwks_path, &config.jwks_url, &config.jwt_issuer) {
+            (Some(path), _, _) => JwksSource::File(path.clone()),
+            (None, Some(url), _) => JwksSource::Url(url.clone()),
+            (None, None, Some(issuer)) => JwksSource::Url(format!(
+                "{}/.well-known/jwks.json",
+                issuer.trim_end_matches('/')
+            )),
+            // Config::load で検証済み
+            (None, None, None) => unreachable!("JWKS source is not configured"),
+        };
+
+        Self {
+            jwks: Jwks::new(source),
+            issuer: config.jwt_issuer.clone(),
+            audience: config.jwt_audience.clone(),
+            auto_provision: config.auth_auto_provision,
+        }
+    }
+
+    pub async fn verify(&self, token: &str) -> Result<Claims, AuthError> {
+        let header = decode_header(token).map_err(|e| AuthError::InvalidToken(e.to_string()))?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            return Err(AuthError::InvalidToken(format!("unsupported algorithm {:?}", header.alg)));
+        }
+        let kid = header
+            .kid
+            .ok_or_else(|| AuthError::InvalidToken("missing kid".to_string()))?;
+        let key = self.jwks.decoding_key(&kid).await?;
+
+        let mut validation = Validation::new(header.alg);
+        // aud / client_id はトークンの種類で格納先が異なるため、下で個別に確認する
+        validation.validate_aud = false;
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+
+        let claims = decode::<Claims>(token, &key, &validation)
+            .map_err(|e| AuthError::InvalidToken(e.to_string()))?
+            .claims;
+
+        if let Some(audience) = &self.audience {
+            if !claims.has_audience(audience) {
+                return Err(AuthError::InvalidToken("audience mismatch".to_string()));
+            }
+        }
+
+        Ok(claims)
+    }
+}
+
+fn bearer_token(parts: &Parts) -> Result<&str, AuthError> {
+    parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+        .ok_or(AuthError::MissingToken)
+}
+
+async fn find_user(pool: &PgPool, cognito_sub: &str) -> Result<Option<UserInfo>, sqlx::Error> {
+    sqlx::query_as!(
+        UserInfo,
+        r#"
+        SELECT id, username, email, role as "role: UserRole", is_active
+        FROM users WHERE cognito_sub = $1
+        "#,
+        cognito_sub
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+// 初回アクセスのユーザーを users テーブルに登録する
+// 同時に初回アクセスが来た場合に備え、cognito_sub の重複は無視して登録済みの行を読み直す
+async fn provision_user(pool: &PgPool, claims: &Claims) -> Result<Option<UserInfo>, sqlx::Error> {
+    // email は NOT NULL UNIQUE のため、アクセストークンなどで取得できない場合は sub から仮の値を作る
+    let email = claims
+        .email
+        .clone()
+        .unwrap_or_else(|| format!("{}@users.invalid", claims.sub));
+
+    sqlx::query!(
+        r#"
+        INSERT INTO users (cognito_sub, username, email)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (cognito_sub) DO NOTHING
+        "#,
+        claims.sub,
+        claims.username(),
+        email
+    )
+    .execute(pool)
+    .await?;
+
+    find_user(pool, &claims.sub).await
+}
+
+// ハンドラの引数に `user: UserInfo` を取ると、認証済みのユーザーが渡される
+#[async_trait]
+impl FromRequestParts<AppState> for UserInfo {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let claims = match bearer_token(parts) {
+            Ok(token) => state.auth.verify(token).await,
+            Err(e) => Err(e),
+        }
+        .map_err(|e| {
+            eprintln!("Authentication failed: {}", e);
+            e.status()
+        })?;
+
+        let user = find_user(&state.db, &claims.sub).await.map_err(|e| {
+            eprintln!("Failed to look up user {}: {}", claims.sub, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+        match user {
+            Some(user) => Ok(user),
+            None if state.auth.auto_provision => provision_user(&state.db, &claims)
+                .await
+                .map_err(|e| {
+                    eprintln!("Failed to provision user {}: {}", claims.sub, e);
+                    // username・email が他のユーザーと重複している場合は管理者による対応が必要
+                    match e.as_database_error() {
+                        Some(db) if db.is_unique_violation() => StatusCode::FORBIDDEN,
+                        _ => StatusCode::INTERNAL_SERVER_ERROR,
+                    }
+                })?
+                .ok_or(StatusCode::INTERNAL_SERVER_ERROR),
+            None => {
+                eprintln!("User {} is not registered", claims.sub);
+                Err(StatusCode::FORBIDDEN)
+            }
+        }
+    }
+}
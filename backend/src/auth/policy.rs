@@ -0,0 +1,144 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+use std::marker::PhantomData;
+
+use crate::{
+    models::{UserInfo, UserRole},
+    AppState,
+};
+
+// エンドポイントごとに要求する操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    // 画像・アノテーションの閲覧、検索
+    Read,
+    // アノテーションの作成・編集・削除
+    Annotate,
+    // 画像のアップロード
+    Upload,
+    // データセットの作成・エクスポート
+    Export,
+    DeleteImages,
+    ManageLabels,
+    ManageUsers,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::Read => "read",
+            Permission::Annotate => "annotate",
+            Permission::Upload => "upload",
+            Permission::Export => "export",
+            Permission::DeleteImages => "delete images",
+            Permission::ManageLabels => "manage labels",
+            Permission::ManageUsers => "manage users",
+        }
+    }
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Admin => "admin",
+            UserRole::Annotator => "annotator",
+            UserRole::Viewer => "viewer",
+        }
+    }
+
+    // ロールごとに許可する操作（管理者はすべて許可）
+    pub fn allows(&self, permission: Permission) -> bool {
+        match self {
+            UserRole::Admin => true,
+            UserRole::Annotator => matches!(
+                permission,
+                Permission::Read | Permission::Annotate | Permission::Upload
+            ),
+            UserRole::Viewer => permission == Permission::Read,
+        }
+    }
+}
+
+// `Authorized<P>` の型引数に使う、要求する操作を表すマーカー型
+pub trait Policy: Send + Sync {
+    const PERMISSION: Permission;
+}
+
+macro_rules! policies {
+    ($($name:ident),* $(,)?) => {
+        $(
+            pub struct $name;
+
+            impl Policy for $name {
+                const PERMISSION: Permission = Permission::$name;
+            }
+        )*
+    };
+}
+
+policies!(Read, Annotate, Upload, Export, DeleteImages, ManageLabels, ManageUsers);
+
+// 403 Forbidden と、拒否した理由をJSONで返す
+#[derive(Debug)]
+pub struct Forbidden {
+    pub reason: String,
+}
+
+impl IntoResponse for Forbidden {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::FORBIDDEN,
+            Json(json!({ "error": "forbidden", "reason": self.reason })),
+        )
+            .into_response()
+    }
+}
+
+pub fn authorize(user: &UserInfo, permission: Permission) -> Result<(), Forbidden> {
+    // 無効化されたユーザーは閲覧のみ許可する
+    if !user.is_active && permission != Permission::Read {
+        return Err(Forbidden {
+            reason: "user is deactivated".to_string(),
+        });
+    }
+
+    if user.role.allows(permission) {
+        Ok(())
+    } else {
+        Err(Forbidden {
+            reason: format!(
+                "role '{}' is not allowed to {}",
+                user.role.as_str(),
+                permission.as_str()
+            ),
+        })
+    }
+}
+
+// ハンドラの引数に `Authorized(user, _): Authorized<Annotate>` のように取ると、
+// 認証に加えてロールが操作を許可されているかを確認する
+pub struct Authorized<P: Policy>(pub UserInfo, pub PhantomData<P>);
+
+#[async_trait]
+impl<P: Policy> FromRequestParts<AppState> for Authorized<P> {
+    type Rejection = Response;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Self::Rejection> {
+        let user = UserInfo::from_request_parts(parts, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        authorize(&user, P::PERMISSION).map_err(|e| {
+            eprintln!("Denied {} for user {}: {}", P::PERMISSION.as_str(), user.id, e.reason);
+            e.into_response()
+        })?;
+
+        Ok(Authorized(user, PhantomData))
+    }
+}
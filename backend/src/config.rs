@@ -0,0 +1,291 @@
+use std::{env, fmt, fs, path::PathBuf, str::FromStr};
+
+// 設定ファイルのパスを指定する環境変数（未指定時はカレントディレクトリの config.toml を探す）
+const CONFIG_FILE_ENV: &str = "CONFIG_FILE";
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+// アプリケーション全体の設定
+// 各キーは「環境変数 > 設定ファイル(TOML) > デフォルト値」の優先順位で解決する。
+// TOMLのキー名は環境変数名を小文字にしたもの（例: DATABASE_URL -> database_url）
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub database_url: String,
+    pub database_max_connections: u32,
+    pub auto_migrate: bool,
+    pub storage_backend: StorageBackend,
+    pub s3_bucket: String,
+    pub aws_region: String,
+    pub local_storage_path: PathBuf,
+    pub storage_signing_secret: String,
+    pub public_base_url: String,
+    pub ai_service_url: String,
+    pub export_worker: bool,
+    pub export_url_expires_secs: u64,
+    pub export_concurrency: usize,
+    pub image_restore_window_secs: u64,
+    pub max_upload_bytes: usize,
+    pub bake_image_orientation: bool,
+    pub image_delivery: ImageDelivery,
+    pub image_url_expires_secs: u64,
+    pub jwks_path: Option<PathBuf>,
+    pub jwks_url: Option<String>,
+    pub jwt_issuer: Option<String>,
+    pub jwt_audience: Option<String>,
+    pub auth_auto_provision: bool,
+    pub host: String,
+    pub port: u16,
+}
+
+// 画像などの保存先
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageBackend {
+    S3,
+    Local,
+}
+
+impl FromStr for StorageBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "s3" => Ok(StorageBackend::S3),
+            "local" => Ok(StorageBackend::Local),
+            other => Err(format!("expected \"s3\" or \"local\", got \"{}\"", other)),
+        }
+    }
+}
+
+// GET /api/images/:id の配信方法
+// proxy: バックエンドがストレージから読み出して返す、redirect: 期限付きURLへリダイレクトする
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageDelivery {
+    Proxy,
+    Redirect,
+}
+
+impl FromStr for ImageDelivery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "proxy" => Ok(ImageDelivery::Proxy),
+            "redirect" => Ok(ImageDelivery::Redirect),
+            other => Err(format!("expected \"proxy\" or \"redirect\", got \"{}\"", other)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    // 設定ファイルの読み込み・パースに失敗
+    File { path: PathBuf, message: String },
+    // 必須キーの欠落や値の不正（全件まとめて報告する）
+    Invalid {
+        missing: Vec<&'static str>,
+        invalid: Vec<String>,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::File { path, message } => {
+                write!(f, "failed to load config file {}: {}", path.display(), message)
+            }
+            ConfigError::Invalid { missing, invalid } => {
+                writeln!(f, "invalid configuration:")?;
+                for key in missing {
+                    writeln!(f, "  - {} must be set", key)?;
+                }
+                for problem in invalid {
+                    writeln!(f, "  - {}", problem)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// `migrate` サブコマンド用のデータベースの設定のみ（認証・ストレージの設定が無くても動かせる）
+pub struct DatabaseConfig {
+    pub database_url: String,
+    pub database_max_connections: u32,
+}
+
+impl DatabaseConfig {
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut loader = Loader::new()?;
+        let config = DatabaseConfig {
+            database_url: loader.required("DATABASE_URL"),
+            database_max_connections: loader.optional("DATABASE_MAX_CONNECTIONS", 5),
+        };
+        loader.finish()?;
+        Ok(config)
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut loader = Loader::new()?;
+
+        let storage_backend = loader.optional("STORAGE_BACKEND", StorageBackend::S3);
+        let (s3_bucket, storage_signing_secret) = match storage_backend {
+            StorageBackend::S3 => (loader.required("S3_BUCKET_NAME"), String::new()),
+            StorageBackend::Local => (
+                loader.optional("S3_BUCKET_NAME", String::new()),
+                loader.required("STORAGE_SIGNING_SECRET"),
+            ),
+        };
+
+        // JWKSの取得元: ローカルファイル > URL > 発行者(Cognito)の .well-known/jwks.json
+        let jwks_path = loader.maybe("JWKS_PATH");
+        let jwks_url = loader.maybe("JWKS_URL");
+        let jwt_issuer: Option<String> = loader.maybe("JWT_ISSUER");
+        if jwks_path.is_none() && jwks_url.is_none() && jwt_issuer.is_none() {
+            loader.missing.push("JWKS_PATH, JWKS_URL or JWT_ISSUER");
+        }
+
+        let config = Config {
+            database_url: loader.required("DATABASE_URL"),
+            database_max_connections: loader.optional("DATABASE_MAX_CONNECTIONS", 5),
+            auto_migrate: loader.optional("AUTO_MIGRATE", false),
+            storage_backend,
+            s3_bucket,
+            aws_region: loader.optional("AWS_REGION", "ap-northeast-1".to_string()),
+            local_storage_path: loader.optional("LOCAL_STORAGE_PATH", PathBuf::from("./storage")),
+            storage_signing_secret,
+            public_base_url: loader.optional("PUBLIC_BASE_URL", "http://localhost:3002".to_string()),
+            ai_service_url: loader.optional("AI_SERVICE_URL", "http://localhost:8001".to_string()),
+            export_worker: loader.optional("EXPORT_WORKER", true),
+            export_url_expires_secs: loader.optional("EXPORT_URL_EXPIRES_SECS", 3600),
+            export_concurrency: loader.optional("EXPORT_CONCURRENCY", 8),
+            image_restore_window_secs: loader.optional("IMAGE_RESTORE_WINDOW_SECS", 7 * 24 * 3600),
+            max_upload_bytes: loader.optional("MAX_UPLOAD_BYTES", 100 * 1024 * 1024),
+            bake_image_orientation: loader.optional("BAKE_IMAGE_ORIENTATION", false),
+            image_delivery: loader.optional("IMAGE_DELIVERY", ImageDelivery::Proxy),
+            image_url_expires_secs: loader.optional("IMAGE_URL_EXPIRES_SECS", 300),
+            jwks_path,
+            jwks_url,
+            jwt_issuer,
+            jwt_audience: loader.maybe("JWT_AUDIENCE"),
+            auth_auto_provision: loader.optional("AUTH_AUTO_PROVISION", true),
+            host: loader.optional("HOST", "0.0.0.0".to_string()),
+            port: loader.optional("PORT", 3002),
+        };
+
+        loader.finish()?;
+        Ok(config)
+    }
+
+    // AIサービスのエンドポイントURLを組み立てる
+    pub fn ai_service_endpoint(&self, path: &str) -> String {
+        format!("{}/{}", self.ai_service_url.trim_end_matches('/'), path.trim_start_matches('/'))
+    }
+}
+
+fn load_config_file() -> Result<toml::Table, ConfigError> {
+    let (path, explicit) = match env::var(CONFIG_FILE_ENV) {
+        Ok(path) => (PathBuf::from(path), true),
+        Err(_) => (PathBuf::from(DEFAULT_CONFIG_FILE), false),
+    };
+
+    // 明示的に指定されたファイルが無い場合のみエラーにする
+    if !explicit && !path.exists() {
+        return Ok(toml::Table::new());
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| ConfigError::File {
+        path: path.clone(),
+        message: e.to_string(),
+    })?;
+
+    content.parse::<toml::Table>().map_err(|e| ConfigError::File {
+        path,
+        message: e.to_string(),
+    })
+}
+
+struct Loader {
+    file: toml::Table,
+    missing: Vec<&'static str>,
+    invalid: Vec<String>,
+}
+
+impl Loader {
+    fn new() -> Result<Self, ConfigError> {
+        Ok(Loader {
+            file: load_config_file()?,
+            missing: Vec::new(),
+            invalid: Vec::new(),
+        })
+    }
+
+    // 欠落・不正な値をまとめて報告する
+    fn finish(self) -> Result<(), ConfigError> {
+        if self.missing.is_empty() && self.invalid.is_empty() {
+            return Ok(());
+        }
+        Err(ConfigError::Invalid {
+            missing: self.missing,
+            invalid: self.invalid,
+        })
+    }
+
+    fn lookup(&self, key: &str) -> Option<String> {
+        if let Ok(value) = env::var(key) {
+            return Some(value);
+        }
+        self.file.get(&key.to_lowercase()).map(|value| match value {
+            toml::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+    }
+
+    fn parse<T: FromStr>(&mut self, key: &'static str, raw: &str) -> Option<T>
+    where
+        T::Err: fmt::Display,
+    {
+        match raw.parse::<T>() {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.invalid.push(format!("{} has an invalid value {:?}: {}", key, raw, e));
+                None
+            }
+        }
+    }
+
+    fn required<T: FromStr + Default>(&mut self, key: &'static str) -> T
+    where
+        T::Err: fmt::Display,
+    {
+        match self.lookup(key) {
+            Some(raw) if !raw.is_empty() => self.parse(key, &raw).unwrap_or_default(),
+            _ => {
+                self.missing.push(key);
+                T::default()
+            }
+        }
+    }
+
+    fn optional<T: FromStr>(&mut self, key: &'static str, default: T) -> T
+    where
+        T::Err: fmt::Display,
+    {
+        match self.lookup(key) {
+            Some(raw) if !raw.is_empty() => self.parse(key, &raw).unwrap_or(default),
+            _ => default,
+        }
+    }
+
+    fn maybe<T: FromStr>(&mut self, key: &'static str) -> Option<T>
+    where
+        T::Err: fmt::Display,
+    {
+        match self.lookup(key) {
+            Some(raw) if !raw.is_empty() => self.parse(key, &raw),
+            _ => None,
+        }
+    }
+}
@@ -1,6 +1,8 @@
-use axum::{extract::Path, http::StatusCode, response::Json, extract::State};
+use axum::{extract::Path, http::StatusCode, response::{IntoResponse, Json, Response}, extract::State};
 use uuid::Uuid;
 use crate::{
+    auth::policy::{Annotate, Authorized, Read},
+    handlers::label::check_annotation_label,
     models::{Annotation, CreateAnnotationRequest, UpdateAnnotationRequest, CreateAnnotationResponse},
     AppState,
     utils::json::JsonExtractor,
@@ -9,33 +11,28 @@ use crate::{
 // 新しいアノテーション作成
 pub async fn create_annotation(
     State(state): State<AppState>,
+    Authorized(user, _): Authorized<Annotate>,
     JsonExtractor(payload): JsonExtractor<CreateAnnotationRequest>,
-) -> Result<Json<CreateAnnotationResponse>, StatusCode> {
+) -> Result<Json<CreateAnnotationResponse>, Response> {
     // 画像が存在するか確認
     let image_exists = sqlx::query_scalar!(
-        "SELECT EXISTS(SELECT 1 FROM images WHERE id = $1)",
+        "SELECT EXISTS(SELECT 1 FROM images WHERE id = $1 AND deleted_at IS NULL)",
         payload.image_id
     )
     .fetch_one(&state.db)
     .await
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())?;
 
     if !image_exists.unwrap_or(false) {
         eprintln!("Image not found: {}", payload.image_id);
-        return Err(StatusCode::NOT_FOUND);
+        return Err(StatusCode::NOT_FOUND.into_response());
     }
 
+    // labels に登録済みで、アーカイブされていないラベルのみ使える
+    check_annotation_label(&state.db, &payload.label, None).await?;
+
     let id = Uuid::new_v4();
     let now = chrono::Utc::now();
-    
-    // TODO: 認証からuser_idを取得（現在は仮のUUID）
-    let user_id: Uuid = sqlx::query_scalar("SELECT id FROM users LIMIT 1")
-        .fetch_one(&state.db)
-        .await
-        .map_err(|e| {
-            eprintln!("Failed to fetch a user from the database. Is it seeded? Error: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
-        })?;
 
     // Bboxをリクエストのx,y,width,heightから作成
     let bbox = vec![payload.x, payload.y, payload.width, payload.height];
@@ -48,7 +45,7 @@ pub async fn create_annotation(
     )
     .bind(id)
     .bind(payload.image_id)
-    .bind(user_id)
+    .bind(user.id)
     .bind(payload.annotation_type)
     .bind(payload.x)
     .bind(payload.y)
@@ -65,7 +62,7 @@ pub async fn create_annotation(
     .await
     .map_err(|e| {
         eprintln!("Failed to create annotation: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
     })?;
 
     Ok(Json(CreateAnnotationResponse { id }))
@@ -74,6 +71,7 @@ pub async fn create_annotation(
 // 画像に紐づくアノテーション全取得
 pub async fn get_annotations_for_image(
     State(state): State<AppState>,
+    _: Authorized<Read>,
     Path(image_id): Path<Uuid>,
 ) -> Result<Json<Vec<Annotation>>, StatusCode> {
     sqlx::query_as!(
@@ -103,6 +101,7 @@ pub async fn get_annotations_for_image(
 // アノテーション取得
 pub async fn get_annotation(
     State(state): State<AppState>,
+    _: Authorized<Read>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Annotation>, StatusCode> {
     sqlx::query_as!(
@@ -129,9 +128,22 @@ pub async fn get_annotation(
 // アノテーション更新
 pub async fn update_annotation(
     State(state): State<AppState>,
+    _: Authorized<Annotate>,
     Path(id): Path<Uuid>,
     JsonExtractor(payload): JsonExtractor<UpdateAnnotationRequest>,
-) -> Result<StatusCode, StatusCode> {
+) -> Result<StatusCode, Response> {
+    let current_label = sqlx::query_scalar!("SELECT label FROM annotations WHERE id = $1", id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to fetch annotation: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+    let Some(current_label) = current_label else {
+        return Ok(StatusCode::NOT_FOUND);
+    };
+    check_annotation_label(&state.db, &payload.label, Some(&current_label)).await?;
+
     let result = sqlx::query(
         r#"
         UPDATE annotations
@@ -160,7 +172,7 @@ pub async fn update_annotation(
         Ok(_) => Ok(StatusCode::NOT_FOUND),
         Err(e) => {
             eprintln!("Failed to update annotation: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
         }
     }
 }
@@ -168,6 +180,7 @@ pub async fn update_annotation(
 // アノテーション削除
 pub async fn delete_annotation(
     State(state): State<AppState>,
+    _: Authorized<Annotate>,
     Path(id): Path<Uuid>,
 ) -> Result<StatusCode, StatusCode> {
     let result = sqlx::query("DELETE FROM annotations WHERE id = $1")
@@ -190,10 +203,12 @@ pub struct LabelsResponse {
     labels: Vec<String>,
 }
 
+// アノテーションに使えるラベル（アーカイブしていないもの）の名前
 pub async fn get_available_labels(
     State(state): State<AppState>,
+    _: Authorized<Read>,
 ) -> Result<Json<LabelsResponse>, StatusCode> {
-    let labels = sqlx::query_scalar("SELECT DISTINCT label FROM annotations ORDER BY label")
+    let labels = sqlx::query_scalar("SELECT DISTINCT name FROM labels WHERE NOT is_archived ORDER BY name")
         .fetch_all(&state.db)
         .await
         .map_err(|e| {
@@ -1,46 +1,345 @@
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
 };
-use sqlx::postgres::PgRow;
+use serde_json::json;
+use sqlx::PgConnection;
 
 use uuid::Uuid;
 
 use crate::{
-    models::{CreateDatasetRequest, CreateDatasetResponse, DatasetFormat},
+    auth::policy::{Authorized, Export, Read},
+    handlers::export::{download_url, insert_export_job, start_export_job, CreateExportJobRequest},
+    models::{
+        CreateDatasetRequest, CreateDatasetResponse, Dataset, DatasetDetail, DatasetFormat,
+        DatasetImagesRequest, DatasetStatus, UpdateDatasetRequest,
+    },
     AppState,
 };
 
+fn internal_error(context: &str, e: sqlx::Error) -> Response {
+    eprintln!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+// 空・空白のみの名前は使えない
+fn blank_name() -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": "name must not be blank" }))).into_response()
+}
+
+// images に存在しない（または削除済みの）画像IDを返す
+async fn find_unknown_images(conn: &mut PgConnection, image_ids: &[Uuid]) -> Result<Vec<Uuid>, sqlx::Error> {
+    let known = sqlx::query_scalar!("SELECT id FROM images WHERE id = ANY($1) AND deleted_at IS NULL", image_ids)
+        .fetch_all(conn)
+        .await?;
+    Ok(image_ids.iter().filter(|id| !known.contains(id)).copied().collect())
+}
+
+// 存在しない画像IDが含まれていれば 400 と該当IDの一覧を返す
+async fn ensure_images_exist(conn: &mut PgConnection, image_ids: &[Uuid]) -> Result<(), Response> {
+    let unknown = find_unknown_images(conn, image_ids)
+        .await
+        .map_err(|e| internal_error("Failed to check image ids", e))?;
+
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    Err((
+        StatusCode::BAD_REQUEST,
+        Json(json!({ "error": "unknown image ids", "image_ids": unknown })),
+    )
+        .into_response())
+}
+
+async fn fetch_dataset(conn: &mut PgConnection, id: Uuid) -> Result<Option<Dataset>, sqlx::Error> {
+    sqlx::query_as!(
+        Dataset,
+        r#"
+        SELECT
+            d.id, d.name, d.description,
+            d.format as "format: DatasetFormat",
+            d.status as "status: DatasetStatus",
+            (
+                SELECT COUNT(*) FROM dataset_images di JOIN images i ON i.id = di.image_id
+                WHERE di.dataset_id = d.id AND i.deleted_at IS NULL
+            ) as "image_count!",
+            d.created_at as "created_at!",
+            d.updated_at as "updated_at!"
+        FROM datasets d WHERE d.id = $1
+        "#,
+        id
+    )
+    .fetch_optional(conn)
+    .await
+}
+
 // create_dataset ハンドラ
+// データセットと所属画像、そのエクスポートジョブを1トランザクションで登録する
 pub async fn create_dataset(
     State(state): State<AppState>,
+    Authorized(user, _): Authorized<Export>,
     Json(payload): Json<CreateDatasetRequest>,
-) -> Result<Json<CreateDatasetResponse>, StatusCode> {
-    println!("📦 Creating dataset: {}", payload.name);
-    let dataset_id = Uuid::new_v4();
-    let s3_key = format!("datasets/{}/{}.zip", dataset_id, payload.name);
-    
-    Ok(Json(CreateDatasetResponse {
-        id: dataset_id,
-        name: payload.name,
-        format: payload.format,
-        download_url: format!("https://s3.{}.amazonaws.com/{}/{}", "ap-northeast-1", state.s3_bucket, s3_key),
-    }))
-}
-
-// エクスポート用のヘルパー関数 (今は空)
-fn export_dataset_yolo(_dataset_content: &[(PgRow, Vec<PgRow>)]) -> (String, Vec<u8>) {
-    let s3_key = format!("datasets/{}/yolo.zip", Uuid::new_v4());
-    (s3_key, Vec::new())
-}
-
-fn export_dataset_coco(_dataset_content: &[(PgRow, Vec<PgRow>)]) -> (String, Vec<u8>) {
-    let s3_key = format!("datasets/{}/coco.zip", Uuid::new_v4());
-    (s3_key, Vec::new())
-}
-
-fn export_dataset_voc(_dataset_content: &[(PgRow, Vec<PgRow>)]) -> (String, Vec<u8>) {
-    let s3_key = format!("datasets/{}/voc.zip", Uuid::new_v4());
-    (s3_key, Vec::new())
-}
\ No newline at end of file
+) -> Result<(StatusCode, Json<CreateDatasetResponse>), Response> {
+    if payload.name.trim().is_empty() {
+        return Err(blank_name());
+    }
+
+    let mut tx = state.db.begin().await.map_err(|e| internal_error("Failed to begin transaction", e))?;
+
+    ensure_images_exist(&mut tx, &payload.image_ids).await?;
+
+    let dataset_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO datasets (name, description, format)
+        VALUES ($1, $2, $3)
+        RETURNING id
+        "#,
+        payload.name,
+        payload.description,
+        payload.format as DatasetFormat
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| internal_error("Failed to create dataset", e))?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO dataset_images (dataset_id, image_id)
+        SELECT $1, image_id FROM UNNEST($2::uuid[]) AS image_id
+        ON CONFLICT DO NOTHING
+        "#,
+        dataset_id,
+        &payload.image_ids
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| internal_error("Failed to add images to dataset", e))?;
+
+    let export_request = CreateExportJobRequest {
+        dataset_id: Some(dataset_id),
+        ..Default::default()
+    };
+    let job = insert_export_job(&mut tx, user.id, &payload.name, payload.format, &export_request)
+        .await
+        .map_err(|e| internal_error("Failed to create export job", e))?;
+
+    tx.commit().await.map_err(|e| internal_error("Failed to commit dataset", e))?;
+
+    start_export_job(&state, job.id);
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateDatasetResponse {
+            id: dataset_id,
+            name: payload.name,
+            format: payload.format,
+            export_job_id: job.id,
+            download_url: Some(download_url(&state.config, job.id)),
+        }),
+    ))
+}
+
+// データセット一覧
+pub async fn list_datasets(
+    State(state): State<AppState>,
+    _: Authorized<Read>,
+) -> Result<Json<Vec<Dataset>>, Response> {
+    sqlx::query_as!(
+        Dataset,
+        r#"
+        SELECT
+            d.id, d.name, d.description,
+            d.format as "format: DatasetFormat",
+            d.status as "status: DatasetStatus",
+            (
+                SELECT COUNT(*) FROM dataset_images di JOIN images i ON i.id = di.image_id
+                WHERE di.dataset_id = d.id AND i.deleted_at IS NULL
+            ) as "image_count!",
+            d.created_at as "created_at!",
+            d.updated_at as "updated_at!"
+        FROM datasets d
+        ORDER BY d.created_at DESC, d.id
+        "#
+    )
+    .fetch_all(&state.db)
+    .await
+    .map(Json)
+    .map_err(|e| internal_error("Failed to list datasets", e))
+}
+
+// データセットの詳細（所属する画像IDを含む）
+pub async fn get_dataset(
+    State(state): State<AppState>,
+    _: Authorized<Read>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DatasetDetail>, Response> {
+    let mut conn = state.db.acquire().await.map_err(|e| internal_error("Failed to acquire connection", e))?;
+
+    let dataset = fetch_dataset(&mut conn, id)
+        .await
+        .map_err(|e| internal_error("Failed to fetch dataset", e))?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    let image_ids = sqlx::query_scalar!(
+        r#"
+        SELECT di.image_id as "image_id!" FROM dataset_images di JOIN images i ON i.id = di.image_id
+        WHERE di.dataset_id = $1 AND i.deleted_at IS NULL
+        ORDER BY di.created_at, di.image_id
+        "#,
+        id
+    )
+    .fetch_all(&mut *conn)
+    .await
+    .map_err(|e| internal_error("Failed to fetch dataset images", e))?;
+
+    Ok(Json(DatasetDetail { dataset, image_ids }))
+}
+
+// データセットの名前・説明・状態の変更
+pub async fn update_dataset(
+    State(state): State<AppState>,
+    _: Authorized<Export>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateDatasetRequest>,
+) -> Result<Json<Dataset>, Response> {
+    if payload.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(blank_name());
+    }
+
+    let mut tx = state.db.begin().await.map_err(|e| internal_error("Failed to begin transaction", e))?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE datasets
+        SET
+            name = COALESCE($1, name),
+            description = CASE WHEN $2 THEN $3 ELSE description END,
+            status = COALESCE($4, status),
+            updated_at = NOW()
+        WHERE id = $5
+        "#,
+        payload.name,
+        payload.description.is_some(),
+        payload.description.flatten(),
+        payload.status as Option<DatasetStatus>,
+        id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| internal_error("Failed to update dataset", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+
+    let dataset = fetch_dataset(&mut tx, id)
+        .await
+        .map_err(|e| internal_error("Failed to fetch dataset", e))?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    tx.commit().await.map_err(|e| internal_error("Failed to commit dataset", e))?;
+    Ok(Json(dataset))
+}
+
+// データセットへの画像の追加
+pub async fn add_dataset_images(
+    State(state): State<AppState>,
+    _: Authorized<Export>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<DatasetImagesRequest>,
+) -> Result<Json<Dataset>, Response> {
+    let mut tx = state.db.begin().await.map_err(|e| internal_error("Failed to begin transaction", e))?;
+
+    // 同時に削除されないよう行ロックを取る
+    let exists = sqlx::query_scalar!("SELECT id FROM datasets WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| internal_error("Failed to lock dataset", e))?;
+    if exists.is_none() {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+
+    ensure_images_exist(&mut tx, &payload.image_ids).await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO dataset_images (dataset_id, image_id)
+        SELECT $1, image_id FROM UNNEST($2::uuid[]) AS image_id
+        ON CONFLICT DO NOTHING
+        "#,
+        id,
+        &payload.image_ids
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| internal_error("Failed to add images to dataset", e))?;
+
+    sqlx::query!("UPDATE datasets SET updated_at = NOW() WHERE id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| internal_error("Failed to update dataset", e))?;
+
+    let dataset = fetch_dataset(&mut tx, id)
+        .await
+        .map_err(|e| internal_error("Failed to fetch dataset", e))?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    tx.commit().await.map_err(|e| internal_error("Failed to commit dataset", e))?;
+    Ok(Json(dataset))
+}
+
+// データセットからの画像の削除（画像そのものは削除しない）
+pub async fn remove_dataset_images(
+    State(state): State<AppState>,
+    _: Authorized<Export>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<DatasetImagesRequest>,
+) -> Result<Json<Dataset>, Response> {
+    let mut tx = state.db.begin().await.map_err(|e| internal_error("Failed to begin transaction", e))?;
+
+    let result = sqlx::query!(
+        "UPDATE datasets SET updated_at = NOW() WHERE id = $1",
+        id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| internal_error("Failed to update dataset", e))?;
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+
+    sqlx::query!(
+        "DELETE FROM dataset_images WHERE dataset_id = $1 AND image_id = ANY($2)",
+        id,
+        &payload.image_ids
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| internal_error("Failed to remove images from dataset", e))?;
+
+    let dataset = fetch_dataset(&mut tx, id)
+        .await
+        .map_err(|e| internal_error("Failed to fetch dataset", e))?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    tx.commit().await.map_err(|e| internal_error("Failed to commit dataset", e))?;
+    Ok(Json(dataset))
+}
+
+// データセットの削除（dataset_images は CASCADE で削除される）
+pub async fn delete_dataset(
+    State(state): State<AppState>,
+    _: Authorized<Export>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, Response> {
+    let result = sqlx::query!("DELETE FROM datasets WHERE id = $1", id)
+        .execute(&state.db)
+        .await
+        .map_err(|e| internal_error("Failed to delete dataset", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(StatusCode::NOT_FOUND.into_response());
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
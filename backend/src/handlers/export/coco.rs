@@ -0,0 +1,187 @@
+use axum::http::StatusCode;
+use serde::Serialize;
+
+use super::{export_file_name, write_zip_file, ArchiveWriter, ArchiveZip, ClassList, ImageData};
+use crate::models::{AnnotationSource, AnnotationType, DatasetSplit};
+
+#[derive(Serialize)]
+struct CocoFile<'a> {
+    info: CocoInfo<'a>,
+    images: Vec<CocoImage>,
+    annotations: Vec<CocoAnnotation>,
+    categories: &'a [CocoCategory<'a>],
+}
+
+#[derive(Serialize)]
+struct CocoInfo<'a> {
+    description: &'a str,
+    date_created: String,
+}
+
+#[derive(Serialize)]
+struct CocoImage {
+    id: i64,
+    file_name: String,
+    width: i32,
+    height: i32,
+}
+
+#[derive(Serialize)]
+struct CocoAnnotation {
+    id: i64,
+    image_id: i64,
+    category_id: i64,
+    bbox: [f32; 4],
+    area: f32,
+    // ポリゴンのアノテーションのみ [[x1, y1, x2, y2, ...]]
+    segmentation: Vec<Vec<f32>>,
+    iscrowd: u8,
+    // AIによるアノテーションのみ
+    #[serde(skip_serializing_if = "Option::is_none")]
+    score: Option<f32>,
+}
+
+#[derive(Serialize)]
+struct CocoCategory<'a> {
+    id: i64,
+    name: &'a str,
+    supercategory: &'a str,
+}
+
+// 多角形の面積（靴紐公式）
+fn polygon_area(vertices: &[[f32; 2]]) -> f32 {
+    let twice_area: f32 = vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum();
+    twice_area.abs() / 2.0
+}
+
+// 分割ごとの images・annotations（画像のデータは保持しない）
+struct CocoSubset {
+    split: DatasetSplit,
+    images: Vec<CocoImage>,
+    annotations: Vec<CocoAnnotation>,
+}
+
+// COCO instances 形式
+// images/{train,val,test}/ に画像、annotations/instances_{train,val,test}.json にアノテーションを格納する
+// 画像・アノテーションのIDは、同じデータに対して常に同じ値になるよう画像IDの順に1から連番で振る
+// カテゴリIDはクラスID + 1
+pub(super) struct CocoWriter<'a> {
+    classes: &'a ClassList,
+    dataset_name: &'a str,
+    subsets: Vec<CocoSubset>,
+    annotation_id: i64,
+}
+
+impl<'a> CocoWriter<'a> {
+    pub(super) fn new(classes: &'a ClassList, dataset_name: &'a str, splits: &[DatasetSplit]) -> Self {
+        Self {
+            classes,
+            dataset_name,
+            subsets: splits
+                .iter()
+                .map(|split| CocoSubset {
+                    split: *split,
+                    images: Vec::new(),
+                    annotations: Vec::new(),
+                })
+                .collect(),
+            annotation_id: 0,
+        }
+    }
+}
+
+impl ArchiveWriter for CocoWriter<'_> {
+    fn add_image(&mut self, zip: &mut ArchiveZip, index: usize, data: &ImageData) -> Result<(), StatusCode> {
+        let Some(subset) = self.subsets.iter_mut().find(|subset| subset.split == data.split) else {
+            eprintln!("Image {} has a split that is not exported, skipping.", data.image.id);
+            return Ok(());
+        };
+
+        let image_id = index as i64 + 1;
+        let (base_name, ext) = export_file_name(&data.image);
+        let file_name = format!("{}{}", base_name, ext);
+
+        let mut sorted_annotations: Vec<_> = data.annotations.iter().collect();
+        sorted_annotations.sort_by_key(|ann| (ann.created_at, ann.id));
+
+        for ann in sorted_annotations {
+            let Some(category_index) = self.classes.class_id(&ann.label) else {
+                continue;
+            };
+            // segmentation は polygon のアノテーションのみ（bbox・point に points があっても [] にする）
+            let polygon = match ann.annotation_type {
+                AnnotationType::Polygon => ann.polygon(),
+                AnnotationType::BoundingBox | AnnotationType::Point => None,
+            };
+            let Some(bbox) = ann.bounding_box() else {
+                eprintln!("Annotation {} has neither bbox nor polygon, skipping.", ann.id);
+                continue;
+            };
+
+            self.annotation_id += 1;
+            subset.annotations.push(CocoAnnotation {
+                id: self.annotation_id,
+                image_id,
+                category_id: category_index as i64 + 1,
+                bbox,
+                area: polygon.as_deref().map_or(bbox[2] * bbox[3], polygon_area),
+                segmentation: polygon
+                    .map(|vertices| vec![vertices.into_iter().flatten().collect()])
+                    .unwrap_or_default(),
+                iscrowd: 0,
+                score: match ann.source {
+                    AnnotationSource::Ai => ann.confidence,
+                    AnnotationSource::Manual => None,
+                },
+            });
+        }
+
+        let image_path = format!("{}/images/{}/{}", self.dataset_name, data.split.as_str(), file_name);
+        write_zip_file(zip, &image_path, &data.s3_data)?;
+
+        subset.images.push(CocoImage {
+            id: image_id,
+            file_name,
+            width: data.image.width,
+            height: data.image.height,
+        });
+        Ok(())
+    }
+
+    fn finish(&mut self, zip: &mut ArchiveZip) -> Result<(), StatusCode> {
+        let categories: Vec<CocoCategory> = self
+            .classes
+            .selected()
+            .into_iter()
+            .map(|(i, label)| CocoCategory {
+                id: i as i64 + 1,
+                name: label,
+                supercategory: "none",
+            })
+            .collect();
+
+        for subset in std::mem::take(&mut self.subsets) {
+            let coco = CocoFile {
+                info: CocoInfo {
+                    description: self.dataset_name,
+                    date_created: chrono::Utc::now().to_rfc3339(),
+                },
+                images: subset.images,
+                annotations: subset.annotations,
+                categories: &categories,
+            };
+            let json = serde_json::to_vec_pretty(&coco).map_err(|e| {
+                eprintln!("Failed to serialize COCO annotations: {}", e);
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+
+            let json_path = format!("{}/annotations/instances_{}.json", self.dataset_name, subset.split.as_str());
+            write_zip_file(zip, &json_path, &json)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,483 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Redirect, Response},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{types::Json as SqlJson, PgConnection, PgPool};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use super::{
+    plan_dataset_export, plan_filter_export, write_archive, ExportError, ExportOptions, FilterOptions,
+    CHUNK_QUEUE,
+};
+use crate::{
+    auth::policy::{Authorized, Export},
+    config::Config,
+    models::{DatasetFormat, ExportJob, ExportJobResponse, ExportJobStatus, UserInfo, UserRole},
+    storage::{SignedMethod, StorageError},
+    AppState,
+};
+
+// 新しいジョブを確認する間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+// 実行中のジョブの進捗を記録する間隔
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+// この時間（秒）進捗が更新されない実行中のジョブは、ワーカーが停止したものとして失敗にする
+const STALE_AFTER_SECS: f64 = 600.0;
+const LIST_LIMIT: i64 = 50;
+
+// エクスポートジョブの作成リクエスト
+// dataset_id を指定した場合は永続化されたデータセットを、省略した場合は name・format・filter の条件でエクスポートする
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateExportJobRequest {
+    pub dataset_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub format: Option<DatasetFormat>,
+    pub filter: Option<FilterOptions>,
+    #[serde(flatten)]
+    pub options: ExportOptions,
+}
+
+fn internal_error(context: &str, e: sqlx::Error) -> Response {
+    eprintln!("{}: {}", context, e);
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+// 完了したジョブの成果物をダウンロードするURL（リダイレクトで期限付きURLに転送する）
+pub fn download_url(config: &Config, job_id: Uuid) -> String {
+    format!("{}/api/exports/{}/download", config.public_base_url.trim_end_matches('/'), job_id)
+}
+
+// 成果物のファイル名に使えない文字を置き換える
+fn archive_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect();
+    if sanitized.trim_matches('.').is_empty() {
+        "dataset.zip".to_string()
+    } else {
+        format!("{}.zip", sanitized)
+    }
+}
+
+// ジョブを pending として登録する
+// データセットの作成と同じトランザクションで登録できるよう、接続を受け取る
+pub async fn insert_export_job(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    name: &str,
+    format: DatasetFormat,
+    request: &CreateExportJobRequest,
+) -> Result<ExportJob, sqlx::Error> {
+    sqlx::query_as!(
+        ExportJob,
+        r#"
+        INSERT INTO export_jobs (user_id, dataset_id, name, format, request)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING
+            id, user_id, dataset_id, name,
+            format as "format: DatasetFormat",
+            status as "status: ExportJobStatus",
+            processed_images, total_images, object_key, error,
+            created_at, updated_at, started_at, finished_at
+        "#,
+        user_id,
+        request.dataset_id,
+        name,
+        format as DatasetFormat,
+        SqlJson(request) as _
+    )
+    .fetch_one(conn)
+    .await
+}
+
+// 登録したジョブの処理を開始する
+// サーバー内でワーカーを動かさない構成（Lambda）では、別プロセスのワーカーが取り出すまで pending のまま残す
+pub fn start_export_job(state: &AppState, job_id: Uuid) {
+    if state.config.export_worker {
+        tokio::spawn(run_job(state.clone(), job_id));
+    }
+}
+
+// POST /api/exports
+pub async fn create_export_job(
+    State(state): State<AppState>,
+    Authorized(user, _): Authorized<Export>,
+    Json(payload): Json<CreateExportJobRequest>,
+) -> Result<(StatusCode, Json<ExportJobResponse>), Response> {
+    payload.options.validate().map_err(IntoResponse::into_response)?;
+
+    let (name, format) = match payload.dataset_id {
+        Some(dataset_id) => {
+            let dataset = sqlx::query!(
+                r#"SELECT name, format as "format: DatasetFormat" FROM datasets WHERE id = $1"#,
+                dataset_id
+            )
+            .fetch_optional(&state.db)
+            .await
+            .map_err(|e| internal_error("Failed to fetch dataset", e))?
+            .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+            (dataset.name, payload.format.unwrap_or(dataset.format))
+        }
+        None => match (&payload.name, payload.format, &payload.filter) {
+            (Some(name), Some(format), Some(_)) => (name.clone(), format),
+            _ => {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    "name, format and filter are required when dataset_id is omitted",
+                )
+                    .into_response())
+            }
+        },
+    };
+
+    let mut conn = state
+        .db
+        .acquire()
+        .await
+        .map_err(|e| internal_error("Failed to acquire connection", e))?;
+    let job = insert_export_job(&mut conn, user.id, &name, format, &payload)
+        .await
+        .map_err(|e| internal_error("Failed to create export job", e))?;
+
+    start_export_job(&state, job.id);
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(ExportJobResponse {
+            job,
+            download_url: None,
+        }),
+    ))
+}
+
+// 自分のジョブ（管理者はすべてのジョブ）を新しい順に返す
+pub async fn list_export_jobs(
+    State(state): State<AppState>,
+    Authorized(user, _): Authorized<Export>,
+) -> Result<Json<Vec<ExportJobResponse>>, Response> {
+    let jobs = sqlx::query_as!(
+        ExportJob,
+        r#"
+        SELECT
+            id, user_id, dataset_id, name,
+            format as "format: DatasetFormat",
+            status as "status: ExportJobStatus",
+            processed_images, total_images, object_key, error,
+            created_at, updated_at, started_at, finished_at
+        FROM export_jobs
+        WHERE user_id = $1 OR $2
+        ORDER BY created_at DESC, id
+        LIMIT $3
+        "#,
+        user.id,
+        user.role == UserRole::Admin,
+        LIST_LIMIT
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| internal_error("Failed to list export jobs", e))?;
+
+    let mut responses = Vec::with_capacity(jobs.len());
+    for job in jobs {
+        responses.push(job_response(&state, job).await?);
+    }
+    Ok(Json(responses))
+}
+
+// GET /api/exports/:id
+pub async fn get_export_job(
+    State(state): State<AppState>,
+    Authorized(user, _): Authorized<Export>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Json<ExportJobResponse>, Response> {
+    let job = fetch_visible_job(&state.db, job_id, &user).await?;
+    Ok(Json(job_response(&state, job).await?))
+}
+
+// GET /api/exports/:id/download
+// 完了していれば成果物の期限付きURLへリダイレクトし、未完了・失敗なら 409 とジョブの状態を返す
+pub async fn download_export(
+    State(state): State<AppState>,
+    Authorized(user, _): Authorized<Export>,
+    Path(job_id): Path<Uuid>,
+) -> Result<Redirect, Response> {
+    let job = fetch_visible_job(&state.db, job_id, &user).await?;
+    match job_response(&state, job).await? {
+        ExportJobResponse {
+            download_url: Some(url),
+            ..
+        } => Ok(Redirect::temporary(&url)),
+        ExportJobResponse { job, .. } => Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "export is not completed",
+                "status": job.status,
+                "reason": job.error,
+            })),
+        )
+            .into_response()),
+    }
+}
+
+// 他のユーザーのジョブは存在しないものとして扱う（管理者を除く）
+async fn fetch_visible_job(pool: &PgPool, job_id: Uuid, user: &UserInfo) -> Result<ExportJob, Response> {
+    let job = sqlx::query_as!(
+        ExportJob,
+        r#"
+        SELECT
+            id, user_id, dataset_id, name,
+            format as "format: DatasetFormat",
+            status as "status: ExportJobStatus",
+            processed_images, total_images, object_key, error,
+            created_at, updated_at, started_at, finished_at
+        FROM export_jobs WHERE id = $1
+        "#,
+        job_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| internal_error("Failed to fetch export job", e))?;
+
+    match job {
+        Some(job) if job.user_id == user.id || user.role == UserRole::Admin => Ok(job),
+        _ => Err(StatusCode::NOT_FOUND.into_response()),
+    }
+}
+
+async fn job_response(state: &AppState, job: ExportJob) -> Result<ExportJobResponse, Response> {
+    let download_url = match (job.status, &job.object_key) {
+        (ExportJobStatus::Completed, Some(key)) => {
+            let expires_in = Duration::from_secs(state.config.export_url_expires_secs);
+            let url = state
+                .store
+                .signed_url(SignedMethod::Get, key, expires_in)
+                .await
+                .map_err(|e| {
+                    eprintln!("Failed to sign download URL for export {}: {}", job.id, e);
+                    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+                })?;
+            Some(url)
+        }
+        _ => None,
+    };
+    Ok(ExportJobResponse { job, download_url })
+}
+
+// pending のジョブを古い順に1件ずつ処理し続ける
+// サーバー内（EXPORT_WORKER=true）または `kg-annotation-backend export-worker` として動かす
+pub async fn run_worker(state: AppState) {
+    loop {
+        fail_stale_jobs(&state.db).await;
+
+        let next = sqlx::query_scalar!(
+            "SELECT id FROM export_jobs WHERE status = 'pending' ORDER BY created_at LIMIT 1"
+        )
+        .fetch_optional(&state.db)
+        .await;
+
+        match next {
+            Ok(Some(job_id)) => {
+                run_job(state.clone(), job_id).await;
+                continue;
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to poll export jobs: {}", e),
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+// 処理中にプロセスが停止し、進捗が更新されなくなったジョブを失敗にする
+async fn fail_stale_jobs(pool: &PgPool) {
+    let result = sqlx::query!(
+        r#"
+        UPDATE export_jobs
+        SET status = 'failed', error = 'the export worker stopped before finishing',
+            finished_at = NOW(), updated_at = NOW()
+        WHERE status = 'running' AND updated_at < NOW() - make_interval(secs => $1)
+        "#,
+        STALE_AFTER_SECS
+    )
+    .execute(pool)
+    .await;
+
+    match result {
+        Ok(done) if done.rows_affected() > 0 => {
+            eprintln!("Marked {} stale export job(s) as failed", done.rows_affected())
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to check stale export jobs: {}", e),
+    }
+}
+
+async fn run_job(state: AppState, job_id: Uuid) {
+    // 他のワーカーが先に取り出していれば何もしない
+    let claimed = sqlx::query!(
+        r#"
+        UPDATE export_jobs
+        SET status = 'running', started_at = NOW(), updated_at = NOW()
+        WHERE id = $1 AND status = 'pending'
+        RETURNING name, request as "request: SqlJson<CreateExportJobRequest>"
+        "#,
+        job_id
+    )
+    .fetch_optional(&state.db)
+    .await;
+
+    let job = match claimed {
+        Ok(Some(job)) => job,
+        Ok(None) => return,
+        Err(e) => {
+            eprintln!("Failed to claim export job {}: {}", job_id, e);
+            return;
+        }
+    };
+
+    println!("Export job {} started", job_id);
+    let processed = Arc::new(AtomicUsize::new(0));
+    let reporter = tokio::spawn(report_progress(state.db.clone(), job_id, processed.clone()));
+    let result = export_to_store(&state, job_id, &job.name, &job.request, &processed).await;
+    reporter.abort();
+
+    let update = match &result {
+        Ok(object_key) => {
+            println!("Export job {} completed: {}", job_id, object_key);
+            sqlx::query!(
+                r#"
+                UPDATE export_jobs
+                SET status = 'completed', object_key = $2, processed_images = $3,
+                    finished_at = NOW(), updated_at = NOW()
+                WHERE id = $1
+                "#,
+                job_id,
+                object_key,
+                processed.load(Ordering::Relaxed) as i32
+            )
+            .execute(&state.db)
+            .await
+        }
+        Err(e) => {
+            eprintln!("Export job {} failed: {}", job_id, e.message);
+            sqlx::query!(
+                r#"
+                UPDATE export_jobs
+                SET status = 'failed', error = $2, processed_images = $3,
+                    finished_at = NOW(), updated_at = NOW()
+                WHERE id = $1
+                "#,
+                job_id,
+                e.message,
+                processed.load(Ordering::Relaxed) as i32
+            )
+            .execute(&state.db)
+            .await
+        }
+    };
+
+    if let Err(e) = update {
+        eprintln!("Failed to record the result of export job {}: {}", job_id, e);
+    }
+}
+
+// zip を作成してオブジェクトストレージに保存し、保存先のキーを返す
+async fn export_to_store(
+    state: &AppState,
+    job_id: Uuid,
+    name: &str,
+    request: &CreateExportJobRequest,
+    processed: &AtomicUsize,
+) -> Result<String, ExportError> {
+    let plan = match (request.dataset_id, &request.filter, request.format) {
+        (Some(dataset_id), _, format) => plan_dataset_export(&state.db, dataset_id, format, &request.options).await?,
+        (None, Some(filter), Some(format)) => {
+            plan_filter_export(&state.db, name, format, filter, &request.options).await?
+        }
+        _ => {
+            return Err(ExportError::new(
+                StatusCode::BAD_REQUEST,
+                "name, format and filter are required when dataset_id is omitted",
+            ))
+        }
+    };
+
+    sqlx::query!(
+        "UPDATE export_jobs SET total_images = $2, updated_at = NOW() WHERE id = $1",
+        job_id,
+        plan.image_ids.len() as i32
+    )
+    .execute(&state.db)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to update export job {}: {}", job_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let object_key = format!("exports/{}/{}", job_id, archive_file_name(name));
+    let mut upload = state
+        .store
+        .start_upload(&object_key, "application/zip")
+        .await
+        .map_err(|e| upload_error(&object_key, e))?;
+
+    // zip の作成とアップロードを並行して行い、確定した部分から順にアップロードする
+    let (tx, mut rx) = mpsc::channel(CHUNK_QUEUE);
+    let produce = async move { write_archive(state, plan, &tx, Some(processed)).await };
+    let writer = &mut upload;
+    let consume = async move {
+        while let Some(chunk) = rx.recv().await {
+            let chunk = chunk.map_err(|e| StorageError::Backend(e.to_string()))?;
+            writer.write(chunk).await?;
+        }
+        Ok::<_, StorageError>(())
+    };
+    let (produced, uploaded) = tokio::join!(produce, consume);
+
+    if let (Ok(()), Ok(())) = (&produced, &uploaded) {
+        upload.finish().await.map_err(|e| upload_error(&object_key, e))?;
+        return Ok(object_key);
+    }
+
+    if let Err(e) = upload.abort().await {
+        eprintln!("Failed to abort upload of {}: {}", object_key, e);
+    }
+    // アップロードが先に失敗した場合、zip の作成は送信先が閉じたことによるエラーになるため、アップロードのエラーを優先する
+    uploaded.map_err(|e| upload_error(&object_key, e))?;
+    produced?;
+    Ok(object_key)
+}
+
+fn upload_error(object_key: &str, e: StorageError) -> ExportError {
+    eprintln!("Failed to upload export archive {}: {}", object_key, e);
+    ExportError::new(StatusCode::INTERNAL_SERVER_ERROR, "failed to upload the archive")
+}
+
+// 取得済みの画像数を定期的に記録する（updated_at はワーカーの生存確認にも使う）
+async fn report_progress(pool: PgPool, job_id: Uuid, processed: Arc<AtomicUsize>) {
+    let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+    loop {
+        interval.tick().await;
+        let result = sqlx::query!(
+            "UPDATE export_jobs SET processed_images = $2, updated_at = NOW() WHERE id = $1",
+            job_id,
+            processed.load(Ordering::Relaxed) as i32
+        )
+        .execute(&pool)
+        .await;
+
+        if let Err(e) = result {
+            eprintln!("Failed to record progress of export job {}: {}", job_id, e);
+        }
+    }
+}
@@ -0,0 +1,979 @@
+mod coco;
+mod jobs;
+mod split;
+mod voc;
+mod zip_stream;
+
+pub use jobs::{
+    create_export_job, download_export, download_url, get_export_job, insert_export_job,
+    list_export_jobs, run_worker, start_export_job, CreateExportJobRequest,
+};
+pub use split::SplitOptions;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use zip::{write::FileOptions, ZipWriter};
+use std::io::{self, Write};
+use futures::stream::{self, StreamExt};
+use sqlx::PgPool;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::sync::mpsc;
+
+use zip_stream::ZipStream;
+
+use crate::{
+    auth::policy::{Authorized, Export},
+    models::{Annotation, DatasetFormat, DatasetSplit, Image},
+    storage::ObjectStore,
+    AppState,
+};
+
+// labels による画像の絞り込み方
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterType {
+    // 指定したラベルのいずれかを持つ画像
+    #[default]
+    Any,
+    // 指定したラベルをすべて持つ画像
+    All,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FilterOptions {
+    #[serde(rename = "type", default)]
+    pub filter_type: FilterType,
+    // 空の場合はアノテーションのあるすべての画像
+    pub labels: Vec<String>,
+}
+
+// エクスポート形式によらない共通のオプション
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub split: SplitOptions,
+    // ラベルからクラスID（0始まりの連番）への対応
+    // 指定した場合はこのラベルのアノテーションのみを、クラスIDの順に出力する（COCO のカテゴリIDはクラスID + 1）
+    // 省略した場合は filter.labels の順、filter.labels も無ければ labels テーブルの id をクラスIDとする
+    #[serde(default)]
+    pub class_ids: Option<HashMap<String, usize>>,
+}
+
+impl ExportOptions {
+    // class_ids をクラスIDの順に並べたラベルの一覧にする
+    fn class_names(&self) -> Result<Option<Vec<String>>, ExportError> {
+        let Some(class_ids) = &self.class_ids else {
+            return Ok(None);
+        };
+
+        let mut names: Vec<Option<String>> = vec![None; class_ids.len()];
+        for (label, id) in class_ids {
+            match names.get_mut(*id) {
+                Some(slot @ None) => *slot = Some(label.clone()),
+                _ => {
+                    return Err(ExportError::new(
+                        StatusCode::BAD_REQUEST,
+                        "class ids must be unique and numbered from 0 without gaps",
+                    ))
+                }
+            }
+        }
+        Ok(Some(names.into_iter().flatten().collect()))
+    }
+
+    // 同期的に確認できる不正なオプション
+    fn validate(&self) -> Result<(), ExportError> {
+        self.split
+            .ratios()
+            .map_err(|message| ExportError::new(StatusCode::BAD_REQUEST, message))?;
+        self.class_names()?;
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ExportRequest {
+    pub name: String,
+    pub format: DatasetFormat,
+    pub filter: FilterOptions,
+    #[serde(flatten)]
+    pub options: ExportOptions,
+}
+
+// エクスポートできなかった理由
+// 同期エクスポートではそのままレスポンスになり、ジョブでは失敗理由として記録する
+#[derive(Debug)]
+pub struct ExportError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+impl ExportError {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+// 詳細はその場で eprintln 済みの、ステータスコードのみのエラー
+impl From<StatusCode> for ExportError {
+    fn from(status: StatusCode) -> Self {
+        Self::new(status, status.canonical_reason().unwrap_or("export failed"))
+    }
+}
+
+impl IntoResponse for ExportError {
+    fn into_response(self) -> Response {
+        (self.status, self.message).into_response()
+    }
+}
+
+// エクスポートする画像とその分割、クラスの一覧
+struct ExportPlan {
+    name: String,
+    format: DatasetFormat,
+    image_ids: Vec<Uuid>,
+    splits: HashMap<Uuid, DatasetSplit>,
+    classes: ClassList,
+}
+
+// 出力するクラス
+// names はクラスIDの順のクラス名で、出力しないクラスIDも詰めずに含める（YOLO の names は0からの連番のため）
+struct ClassList {
+    names: Vec<String>,
+    // 出力するラベルとそのクラスID
+    ids: HashMap<String, usize>,
+}
+
+impl ClassList {
+    // names のすべてのクラスを出力する
+    fn all(names: Vec<String>) -> Self {
+        let ids = names.iter().enumerate().map(|(id, name)| (name.clone(), id)).collect();
+        Self { names, ids }
+    }
+
+    fn class_id(&self, label: &str) -> Option<usize> {
+        self.ids.get(label).copied()
+    }
+
+    // 出力するクラスIDとラベル（クラスIDの順）
+    fn selected(&self) -> Vec<(usize, &str)> {
+        let mut selected: Vec<(usize, &str)> = self.ids.iter().map(|(name, id)| (*id, name.as_str())).collect();
+        selected.sort();
+        selected
+    }
+
+    fn labels(&self) -> Vec<String> {
+        self.selected().into_iter().map(|(_, name)| name.to_string()).collect()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+struct ImageData {
+    image: Image,
+    s3_data: Vec<u8>,
+    annotations: Vec<Annotation>,
+    split: DatasetSplit,
+}
+
+pub async fn export_dataset(
+    State(state): State<AppState>,
+    _: Authorized<Export>,
+    Json(payload): Json<ExportRequest>,
+) -> Result<Response, ExportError> {
+    let plan = plan_filter_export(&state.db, &payload.name, payload.format, &payload.filter, &payload.options).await?;
+    build_export_response(&state, plan).await
+}
+
+// ラベルで絞り込んだ画像のエクスポート内容を決める
+// 出力するクラスは class_ids、filter.labels（指定した順）、対象の画像のラベル（labels テーブルのクラスID）の順に優先する
+async fn plan_filter_export(
+    pool: &PgPool,
+    name: &str,
+    format: DatasetFormat,
+    filter: &FilterOptions,
+    options: &ExportOptions,
+) -> Result<ExportPlan, ExportError> {
+    let ratios = options
+        .split
+        .ratios()
+        .map_err(|message| ExportError::new(StatusCode::BAD_REQUEST, message))?;
+    let class_names = options.class_names()?;
+
+    let mut filter_labels: Vec<String> = Vec::new();
+    for label in &filter.labels {
+        if !filter_labels.contains(label) {
+            filter_labels.push(label.clone());
+        }
+    }
+
+    let classes = explicit_classes(class_names.clone(), &filter_labels);
+
+    // 絞り込みに使ったラベルのアノテーションが出力から漏れないようにする
+    if let Some(classes) = &classes {
+        if let Some(label) = filter_labels.iter().find(|label| classes.class_id(label).is_none()) {
+            return Err(ExportError::new(
+                StatusCode::BAD_REQUEST,
+                format!("label '{}' is in the filter but has no class id", label),
+            ));
+        }
+    }
+
+    let match_labels = match (&class_names, filter_labels.is_empty()) {
+        (Some(classes), true) => classes.clone(),
+        _ => filter_labels.clone(),
+    };
+
+    // 削除済みの画像は対象にしない
+    let image_ids_result = match (match_labels.is_empty(), filter.filter_type) {
+        (true, _) => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT DISTINCT a.image_id FROM annotations a JOIN images i ON i.id = a.image_id
+                WHERE i.deleted_at IS NULL
+                "#
+            )
+            .fetch_all(pool)
+            .await
+        }
+        (false, FilterType::Any) => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT DISTINCT a.image_id FROM annotations a JOIN images i ON i.id = a.image_id
+                WHERE a.label = ANY($1) AND i.deleted_at IS NULL
+                "#,
+                &match_labels
+            )
+            .fetch_all(pool)
+            .await
+        }
+        (false, FilterType::All) => {
+            sqlx::query_scalar!(
+                r#"
+                SELECT a.image_id FROM annotations a JOIN images i ON i.id = a.image_id
+                WHERE a.label = ANY($1) AND i.deleted_at IS NULL
+                GROUP BY a.image_id
+                HAVING COUNT(DISTINCT a.label) = $2
+                "#,
+                &match_labels,
+                match_labels.len() as i64
+            )
+            .fetch_all(pool)
+            .await
+        }
+    };
+
+    let image_ids: Vec<Uuid> = image_ids_result
+        .map_err(|e| {
+            eprintln!("Failed to query image IDs: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    if image_ids.is_empty() {
+        return Err(ExportError::new(StatusCode::NOT_FOUND, "No images found for the given labels"));
+    }
+
+    let classes = match classes {
+        Some(classes) => classes,
+        None => label_classes(pool, &fetch_labels(pool, &image_ids).await?).await?,
+    };
+
+    if classes.is_empty() {
+        return Err(ExportError::new(StatusCode::NOT_FOUND, "No labels found in the database"));
+    }
+
+    let image_labels = fetch_image_labels(pool, &image_ids, &classes.labels()).await?;
+    let splits = split::assign_splits(&image_labels, &options.split, ratios);
+
+    Ok(ExportPlan {
+        name: name.to_string(),
+        format,
+        image_ids,
+        splits,
+        classes,
+    })
+}
+
+// 画像に付いているラベルの一覧（名前順）
+async fn fetch_labels(pool: &PgPool, image_ids: &[Uuid]) -> Result<Vec<String>, StatusCode> {
+    sqlx::query_scalar!(
+        "SELECT DISTINCT label FROM annotations WHERE image_id = ANY($1) ORDER BY label",
+        image_ids
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch labels: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })
+}
+
+// class_ids・filter.labels で明示したクラス（指定した順に0からのクラスIDを振り、他のクラスは出力しない）
+// どちらも無い場合は None（labels テーブルのクラスIDを使う）
+fn explicit_classes(class_names: Option<Vec<String>>, filter_labels: &[String]) -> Option<ClassList> {
+    match class_names {
+        Some(names) => Some(ClassList::all(names)),
+        None if !filter_labels.is_empty() => Some(ClassList::all(filter_labels.to_vec())),
+        None => None,
+    }
+}
+
+// labels テーブルの id をクラスIDとして、labels のラベルを出力するクラス一覧を作る
+async fn label_classes(pool: &PgPool, labels: &[String]) -> Result<ClassList, StatusCode> {
+    let rows = sqlx::query!("SELECT id, name FROM labels ORDER BY id")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to fetch label taxonomy: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let taxonomy: Vec<(usize, String)> = rows.into_iter().map(|row| (row.id as usize, row.name)).collect();
+    Ok(taxonomy_classes(taxonomy, labels))
+}
+
+// taxonomy（id の昇順のクラスIDと名前）から、labels のラベルを出力するクラス一覧を作る
+// 削除済みのクラスIDは "unused_{id}" という名前で埋め、taxonomy に無いラベルは出力しない
+// 同じ名前のラベルが複数のプロジェクトにある場合は、最も小さい id をクラスIDとする
+fn taxonomy_classes(taxonomy: Vec<(usize, String)>, labels: &[String]) -> ClassList {
+    let class_count = taxonomy.last().map_or(0, |(id, _)| id + 1);
+    let mut names: Vec<String> = (0..class_count).map(|id| format!("unused_{}", id)).collect();
+    for (id, name) in taxonomy {
+        names[id] = name;
+    }
+
+    let mut ids = HashMap::new();
+    for label in labels {
+        match names.iter().position(|name| name == label) {
+            Some(id) => {
+                ids.insert(label.clone(), id);
+            }
+            None => eprintln!("Label '{}' is not in the label taxonomy, skipping.", label),
+        }
+    }
+    ClassList { names, ids }
+}
+
+// データセットのエクスポート時のオプション（format を省略した場合はデータセットの形式を使う）
+#[derive(Deserialize)]
+pub struct DatasetExportRequest {
+    pub format: Option<DatasetFormat>,
+    #[serde(flatten)]
+    pub options: ExportOptions,
+}
+
+// 永続化されたデータセットの画像（dataset_images）をそのままエクスポートする
+// アノテーションの無い画像も空のラベルファイルとして含める
+pub async fn export_persisted_dataset(
+    State(state): State<AppState>,
+    _: Authorized<Export>,
+    Path(dataset_id): Path<Uuid>,
+    Json(payload): Json<DatasetExportRequest>,
+) -> Result<Response, ExportError> {
+    let plan = plan_dataset_export(&state.db, dataset_id, payload.format, &payload.options).await?;
+    build_export_response(&state, plan).await
+}
+
+async fn plan_dataset_export(
+    pool: &PgPool,
+    dataset_id: Uuid,
+    format: Option<DatasetFormat>,
+    options: &ExportOptions,
+) -> Result<ExportPlan, ExportError> {
+    let dataset = sqlx::query!(
+        r#"SELECT name, format as "format: DatasetFormat" FROM datasets WHERE id = $1"#,
+        dataset_id
+    )
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch dataset {}: {}", dataset_id, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
+
+    let members = sqlx::query!(
+        r#"
+        SELECT di.image_id as "image_id!", di.split as "split: DatasetSplit"
+        FROM dataset_images di JOIN images i ON i.id = di.image_id
+        WHERE di.dataset_id = $1 AND i.deleted_at IS NULL
+        "#,
+        dataset_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to query dataset images: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    if members.is_empty() {
+        return Err(ExportError::new(StatusCode::NOT_FOUND, "The dataset has no images"));
+    }
+
+    let ratios = options
+        .split
+        .ratios()
+        .map_err(|message| ExportError::new(StatusCode::BAD_REQUEST, message))?;
+
+    let image_ids: Vec<Uuid> = members.iter().map(|m| m.image_id).collect();
+
+    // class_ids を指定しなかった場合は、データセット内のすべてのラベルを labels テーブルのクラスIDで出力する
+    let classes = match options.class_names()? {
+        Some(names) => ClassList::all(names),
+        None => label_classes(pool, &fetch_labels(pool, &image_ids).await?).await?,
+    };
+
+    // 保存済みの分割はそのまま使い、まだ分割が決まっていない画像（または resplit 時は全画像）だけを振り分ける
+    let mut splits: HashMap<Uuid, DatasetSplit> = if options.split.resplit {
+        HashMap::new()
+    } else {
+        members.iter().filter_map(|m| Some((m.image_id, m.split?))).collect()
+    };
+    let unassigned: Vec<Uuid> = image_ids.iter().filter(|id| !splits.contains_key(id)).copied().collect();
+    if !unassigned.is_empty() {
+        let image_labels = fetch_image_labels(pool, &unassigned, &classes.labels()).await?;
+        let assigned = split::assign_splits(&image_labels, &options.split, ratios);
+        save_splits(pool, dataset_id, &assigned).await?;
+        splits.extend(assigned);
+    }
+
+    Ok(ExportPlan {
+        name: dataset.name,
+        format: format.unwrap_or(dataset.format),
+        image_ids,
+        splits,
+        classes,
+    })
+}
+
+// 送信待ちにできる zip のチャンク数
+const CHUNK_QUEUE: usize = 4;
+
+type ArchiveZip = ZipWriter<ZipStream>;
+
+// 形式ごとの zip の書き方
+// 画像は取得した順に1枚ずつ渡されるので、add_image の中で書き出して手放す
+trait ArchiveWriter: Send {
+    // index は画像IDの順で0から振った連番
+    fn add_image(&mut self, zip: &mut ArchiveZip, index: usize, data: &ImageData) -> Result<(), StatusCode>;
+
+    // すべての画像を書き出した後に書き出すファイル（COCO の JSON など）
+    fn finish(&mut self, zip: &mut ArchiveZip) -> Result<(), StatusCode>;
+}
+
+// zip をストリーミングでレスポンスとして返す（同期エクスポート）
+// 書き出しの途中で失敗した場合はステータスを変えられないため、レスポンスを途中で打ち切る
+async fn build_export_response(state: &AppState, plan: ExportPlan) -> Result<Response, ExportError> {
+    let name = plan.name.clone();
+    let (tx, rx) = mpsc::channel(CHUNK_QUEUE);
+
+    let state = state.clone();
+    tokio::spawn(async move {
+        if let Err(e) = write_archive(&state, plan, &tx, None).await {
+            eprintln!("Failed to stream export archive: {}", e.message);
+            let _ = tx.send(Err(io::Error::other(e.message))).await;
+        }
+    });
+
+    let body = Body::from_stream(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    }));
+
+    let headers = [
+        (header::CONTENT_TYPE, "application/zip".to_string()),
+        (
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.zip\"", name),
+        ),
+    ];
+
+    Ok((headers, body).into_response())
+}
+
+// 画像を並行して取得しながら zip を作成し、確定した部分から順に tx へ送る
+// 同時に取得する画像は EXPORT_CONCURRENCY 枚までで、画像全体をメモリに保持しない
+// progress を渡すと、書き出した画像数を数える
+async fn write_archive(
+    state: &AppState,
+    plan: ExportPlan,
+    tx: &mpsc::Sender<io::Result<Bytes>>,
+    progress: Option<&AtomicUsize>,
+) -> Result<(), ExportError> {
+    let ExportPlan {
+        name,
+        format,
+        mut image_ids,
+        splits,
+        classes,
+    } = plan;
+
+    let output = ZipStream::default();
+    let mut zip = ZipWriter::new(output.clone());
+    // 書き終えたファイルは flush され、以降書き換えられないので送信できる
+    zip.set_flush_on_finish_file(true);
+
+    let archive_splits = export_splits(&splits);
+    let mut writer: Box<dyn ArchiveWriter + '_> = match format {
+        DatasetFormat::Yolo | DatasetFormat::YoloSeg => Box::new(YoloWriter::start(
+            &mut zip,
+            &classes,
+            &name,
+            &archive_splits,
+            format == DatasetFormat::YoloSeg,
+        )?),
+        DatasetFormat::Coco => Box::new(coco::CocoWriter::new(&classes, &name, &archive_splits)),
+        DatasetFormat::Voc => Box::new(voc::VocWriter::new(&classes, &name, &archive_splits)),
+    };
+
+    // 取得の完了順に依存しないよう、画像IDの順に書き出す
+    image_ids.sort();
+    let mut images = stream::iter(image_ids)
+        .map(|image_id| {
+            let split = splits.get(&image_id).copied().unwrap_or(DatasetSplit::Train);
+            fetch_image_data(&state.store, &state.db, image_id, split)
+        })
+        .buffered(state.config.export_concurrency.max(1));
+
+    let mut index = 0;
+    while let Some(data) = images.next().await {
+        let data = data.map_err(|e| {
+            eprintln!("Database or S3 error: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        writer.add_image(&mut zip, index, &data)?;
+        index += 1;
+        if let Some(progress) = progress {
+            progress.fetch_add(1, Ordering::Relaxed);
+        }
+        send_chunk(tx, output.take_flushed()).await?;
+    }
+
+    writer.finish(&mut zip)?;
+    if let Err(e) = zip.finish() {
+        eprintln!("Failed to finish zip file: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
+    send_chunk(tx, output.take_all()).await
+}
+
+async fn send_chunk(tx: &mpsc::Sender<io::Result<Bytes>>, chunk: Bytes) -> Result<(), ExportError> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    tx.send(Ok(chunk)).await.map_err(|_| {
+        ExportError::new(StatusCode::INTERNAL_SERVER_ERROR, "the archive receiver was closed")
+    })
+}
+
+// zip 内のファイルは無圧縮で格納する（画像は圧縮済みのため）
+fn file_options() -> FileOptions<'static, ()> {
+    FileOptions::default().compression_method(zip::CompressionMethod::Stored)
+}
+
+fn write_zip_file(zip: &mut ArchiveZip, path: &str, content: &[u8]) -> Result<(), StatusCode> {
+    if let Err(e) = zip.start_file(path, file_options()) {
+        eprintln!("Failed to create {}: {}", path, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    if let Err(e) = zip.write_all(content) {
+        eprintln!("Failed to write {}: {}", path, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    Ok(())
+}
+
+// 画像ごとの、出力するラベル（分割の層別化に使う）
+async fn fetch_image_labels(
+    pool: &PgPool,
+    image_ids: &[Uuid],
+    labels: &[String],
+) -> Result<Vec<(Uuid, Vec<String>)>, StatusCode> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT image_id, array_agg(DISTINCT label) as "labels!"
+        FROM annotations WHERE image_id = ANY($1) AND label = ANY($2)
+        GROUP BY image_id
+        "#,
+        image_ids,
+        labels
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch image labels: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let mut labels: HashMap<Uuid, Vec<String>> = rows.into_iter().map(|row| (row.image_id, row.labels)).collect();
+    Ok(image_ids
+        .iter()
+        .map(|id| (*id, labels.remove(id).unwrap_or_default()))
+        .collect())
+}
+
+// 決まった分割を dataset_images に保存し、以降のエクスポートでも同じ分割を使う
+async fn save_splits(
+    pool: &PgPool,
+    dataset_id: Uuid,
+    splits: &HashMap<Uuid, DatasetSplit>,
+) -> Result<(), StatusCode> {
+    let (image_ids, split_names): (Vec<Uuid>, Vec<&str>) =
+        splits.iter().map(|(id, split)| (*id, split.as_str())).unzip();
+
+    sqlx::query!(
+        r#"
+        UPDATE dataset_images di
+        SET split = u.split::dataset_split
+        FROM UNNEST($2::uuid[], $3::text[]) AS u(image_id, split)
+        WHERE di.dataset_id = $1 AND di.image_id = u.image_id
+        "#,
+        dataset_id,
+        &image_ids,
+        &split_names as &[&str]
+    )
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to save dataset splits: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    Ok(())
+}
+
+// 1枚分の画像データとアノテーションを取得する
+async fn fetch_image_data(
+    store: &Arc<dyn ObjectStore>,
+    pool: &PgPool,
+    image_id: Uuid,
+    split: DatasetSplit,
+) -> Result<ImageData, sqlx::Error> {
+    let image = sqlx::query_as!(
+        Image,
+        r#"
+        SELECT 
+            id, user_id, filename, original_filename, s3_bucket, s3_key, file_size, 
+            width, height, format, classification_label, created_at as "created_at!", vector
+        FROM images WHERE id = $1
+        "#,
+        image_id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch image data for ID {}: {}", image_id, e);
+        e
+    })?;
+
+    // 設定中のストレージとは別の保存先に登録された画像は読めないため、エクスポート全体を失敗させる
+    if image.s3_bucket != store.bucket() {
+        let message = format!(
+            "image {} is stored in bucket {}, but the configured storage is {}",
+            image_id,
+            image.s3_bucket,
+            store.bucket()
+        );
+        eprintln!("{}", message);
+        return Err(sqlx::Error::Io(std::io::Error::other(message)));
+    }
+
+    let s3_data = store
+        .get(&image.s3_key)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to get object from storage for image {}: {}", image_id, e);
+            sqlx::Error::Io(std::io::Error::other(e.to_string()))
+        })?
+        .to_vec();
+
+    let annotations = sqlx::query_as!(
+        Annotation,
+        r#"
+        SELECT 
+            id, image_id, user_id, 
+            annotation_type as "annotation_type: _",
+            x, y, width, height, points, bbox, label,
+            source as "source: _",
+            confidence,
+            created_at as "created_at!",
+            updated_at as "updated_at!"
+        FROM annotations WHERE image_id = $1
+        "#,
+        image_id
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch annotations for image {}: {}", image_id, e);
+        e
+    })?;
+
+    Ok(ImageData { image, s3_data, annotations, split })
+}
+
+// 出力する分割（train・val は常に出力し、test は該当する画像がある場合のみ）
+fn export_splits(splits: &HashMap<Uuid, DatasetSplit>) -> Vec<DatasetSplit> {
+    let has_test = splits.values().any(|split| *split == DatasetSplit::Test);
+    DatasetSplit::ALL
+        .into_iter()
+        .filter(|split| *split != DatasetSplit::Test || has_test)
+        .collect()
+}
+
+// zip 内で一意になるファイル名（画像IDの短い形式 + 元のファイル名）と拡張子（"." 付き）
+// original_filename はクライアントの申告のため、ディレクトリ部分を除き、空白・区切り文字を "_" にする
+// （VOC の ImageSets は空白区切りで読まれ、"/" は zip 内に余計なディレクトリを作るため）
+fn export_file_name(image: &Image) -> (String, String) {
+    let file_name = image
+        .original_filename
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default();
+    let (base, ext) = match file_name.rsplit_once('.') {
+        Some((base, ext)) => (base, format!(".{}", safe_name_part(ext))),
+        None => (file_name, String::new()),
+    };
+    (format!("{}_{}", image.id.simple(), safe_name_part(base)), ext)
+}
+
+fn safe_name_part(part: &str) -> String {
+    part.chars()
+        .map(|c| if c.is_whitespace() || c.is_control() { '_' } else { c })
+        .collect()
+}
+
+// YOLO検出形式の1行分の座標（中心座標とサイズを0-1の範囲に正規化）
+fn yolo_box_line(ann: &Annotation, image_width: f32, image_height: f32) -> Option<String> {
+    let [x, y, w, h] = ann.bounding_box()?;
+    let x_center = (x + w / 2.0) / image_width;
+    let y_center = (y + h / 2.0) / image_height;
+    let width = w / image_width;
+    let height = h / image_height;
+
+    // 値が0-1の範囲内にあることを確認
+    let in_range = |v: f32| (0.0..=1.0).contains(&v);
+    if [x_center, y_center, width, height].into_iter().all(in_range) {
+        Some(format!("{:.6} {:.6} {:.6} {:.6}", x_center, y_center, width, height))
+    } else {
+        eprintln!(
+            "Invalid normalized coordinates for annotation {}: ({}, {}, {}, {})",
+            ann.id, x_center, y_center, width, height
+        );
+        None
+    }
+}
+
+// Ultralytics のセグメンテーション形式の1行分の座標（頂点を0-1の範囲に正規化して並べる）
+// ポリゴンが無い場合は bbox の四隅を頂点とする
+fn yolo_segment_line(ann: &Annotation, image_width: f32, image_height: f32) -> Option<String> {
+    let vertices = match ann.polygon() {
+        Some(vertices) => vertices,
+        None => {
+            let [x, y, w, h] = ann.bounding_box()?;
+            vec![[x, y], [x + w, y], [x + w, y + h], [x, y + h]]
+        }
+    };
+
+    let coords = vertices
+        .iter()
+        .map(|[x, y]| {
+            format!(
+                "{:.6} {:.6}",
+                (x / image_width).clamp(0.0, 1.0),
+                (y / image_height).clamp(0.0, 1.0)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(coords)
+}
+
+// YOLOv5/v8 形式（images/{split}/・labels/{split}/・data.yaml）
+// segmentation が true の場合はポリゴン（yolo-seg）、false の場合はバウンディングボックスを書き出す
+struct YoloWriter<'a> {
+    classes: &'a ClassList,
+    dataset_name: &'a str,
+    segmentation: bool,
+}
+
+impl<'a> YoloWriter<'a> {
+    // ディレクトリと data.yaml を先に書き出す
+    fn start(
+        zip: &mut ArchiveZip,
+        classes: &'a ClassList,
+        dataset_name: &'a str,
+        splits: &[DatasetSplit],
+        segmentation: bool,
+    ) -> Result<Self, StatusCode> {
+        for kind in ["images", "labels"] {
+            for split in splits {
+                let dir = format!("{}/{}/{}", dataset_name, kind, split.as_str());
+                if let Err(e) = zip.add_directory(&dir, file_options()) {
+                    eprintln!("Failed to create directory {}: {}", dir, e);
+                    return Err(StatusCode::INTERNAL_SERVER_ERROR);
+                }
+            }
+        }
+
+        // YOLOv5/v8形式のdata.yaml内容を書き込み
+        let yaml_content = format!(
+            "path: ..\n{}nc: {}\nnames:\n{}\n",
+            splits
+                .iter()
+                .map(|split| format!("{0}: images/{0}\n", split.as_str()))
+                .collect::<String>(),
+            classes.names.len(),
+            classes.names.iter()
+                .map(|label| format!("  - '{}'", label))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        write_zip_file(zip, &format!("{}/data.yaml", dataset_name), yaml_content.as_bytes())?;
+
+        Ok(Self {
+            classes,
+            dataset_name,
+            segmentation,
+        })
+    }
+}
+
+impl ArchiveWriter for YoloWriter<'_> {
+    fn add_image(&mut self, zip: &mut ArchiveZip, _index: usize, data: &ImageData) -> Result<(), StatusCode> {
+        let subset = data.split.as_str();
+        let (unique_base_name, ext) = export_file_name(&data.image);
+
+        // アノテーションをYOLO形式で書き込み
+        let mut label_content = String::new();
+        for ann in &data.annotations {
+            let Some(label_index) = self.classes.class_id(&ann.label) else {
+                continue;
+            };
+
+            // ゼロ除算を避けるためのチェック
+            if data.image.width <= 0 || data.image.height <= 0 {
+                eprintln!(
+                    "Image {} has invalid dimensions (width: {}, height: {}), skipping annotation.",
+                    data.image.id, data.image.width, data.image.height
+                );
+                continue;
+            }
+
+            let line = if self.segmentation {
+                yolo_segment_line(ann, data.image.width as f32, data.image.height as f32)
+            } else {
+                yolo_box_line(ann, data.image.width as f32, data.image.height as f32)
+            };
+            match line {
+                Some(coords) => label_content.push_str(&format!("{} {}\n", label_index, coords)),
+                None => eprintln!(
+                    "Annotation {} on image {} has no valid shape, skipping.",
+                    ann.id, data.image.id
+                ),
+            }
+        }
+
+        // ラベルファイルと画像ファイルを追加（一意の名前を使用）
+        let label_path = format!("{}/labels/{}/{}.txt", self.dataset_name, subset, unique_base_name);
+        write_zip_file(zip, &label_path, label_content.as_bytes())?;
+
+        let image_path = format!("{}/images/{}/{}{}", self.dataset_name, subset, unique_base_name, ext);
+        write_zip_file(zip, &image_path, &data.s3_data)
+    }
+
+    fn finish(&mut self, _zip: &mut ArchiveZip) -> Result<(), StatusCode> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    fn image_named(original_filename: &str) -> Image {
+        Image {
+            id: Uuid::from_u128(0x1234),
+            user_id: Uuid::nil(),
+            filename: original_filename.to_string(),
+            original_filename: original_filename.to_string(),
+            s3_bucket: "bucket".to_string(),
+            s3_key: "images/key".to_string(),
+            file_size: 0,
+            width: 1,
+            height: 1,
+            format: "image/jpeg".to_string(),
+            classification_label: None,
+            created_at: Utc::now(),
+            vector: None,
+        }
+    }
+
+    #[test]
+    fn export_file_names_have_no_whitespace_or_directories() {
+        let prefix = Uuid::from_u128(0x1234).simple().to_string();
+        let name = |original: &str| export_file_name(&image_named(original));
+
+        assert_eq!(name("IMG 0001.jpg"), (format!("{}_IMG_0001", prefix), ".jpg".to_string()));
+        assert_eq!(name("a\tb .JPG"), (format!("{}_a_b_", prefix), ".JPG".to_string()));
+        assert_eq!(name("dir/sub/photo.png"), (format!("{}_photo", prefix), ".png".to_string()));
+        assert_eq!(name("..\\photo.png"), (format!("{}_photo", prefix), ".png".to_string()));
+        assert_eq!(name("noext"), (format!("{}_noext", prefix), String::new()));
+    }
+
+    #[test]
+    fn filter_labels_are_exported_in_requested_order_only() {
+        let classes = explicit_classes(None, &strings(&["person", "car"])).unwrap();
+
+        assert_eq!(classes.names, strings(&["person", "car"]));
+        assert_eq!(classes.class_id("person"), Some(0));
+        assert_eq!(classes.class_id("car"), Some(1));
+        assert_eq!(classes.labels(), strings(&["person", "car"]));
+    }
+
+    #[test]
+    fn class_ids_take_precedence_over_filter_labels() {
+        let classes = explicit_classes(Some(strings(&["car", "truck", "person"])), &strings(&["person"])).unwrap();
+
+        assert_eq!(classes.names, strings(&["car", "truck", "person"]));
+        assert_eq!(classes.class_id("person"), Some(2));
+    }
+
+    #[test]
+    fn taxonomy_ids_are_used_without_explicit_classes() {
+        assert!(explicit_classes(None, &[]).is_none());
+
+        let taxonomy = vec![(0, "car".to_string()), (2, "person".to_string()), (3, "truck".to_string())];
+        let classes = taxonomy_classes(taxonomy, &strings(&["person", "car", "unknown"]));
+
+        assert_eq!(classes.names, strings(&["car", "unused_1", "person", "truck"]));
+        assert_eq!(classes.class_id("car"), Some(0));
+        assert_eq!(classes.class_id("person"), Some(2));
+        assert_eq!(classes.class_id("truck"), None);
+        assert_eq!(classes.class_id("unknown"), None);
+        assert_eq!(classes.labels(), strings(&["car", "person"]));
+    }
+}
@@ -0,0 +1,234 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::models::DatasetSplit;
+
+// 学習・検証・テストへの分割方法
+// 比率は合計が1でなくてもよい（合計で割って正規化する）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SplitOptions {
+    pub train: f64,
+    pub val: f64,
+    pub test: f64,
+    // 同じ seed と画像IDからは常に同じ分割になる
+    pub seed: u64,
+    // ラベルごとに比率を保つ（少数クラスも各分割に含まれるようにする）
+    pub stratify: bool,
+    // データセットに保存済みの分割を破棄して振り直す
+    pub resplit: bool,
+}
+
+impl Default for SplitOptions {
+    fn default() -> Self {
+        Self {
+            train: 0.8,
+            val: 0.2,
+            test: 0.0,
+            seed: 0,
+            stratify: false,
+            resplit: false,
+        }
+    }
+}
+
+impl SplitOptions {
+    // 正規化した [train, val, test] の比率
+    pub fn ratios(&self) -> Result<[f64; 3], String> {
+        let ratios = [self.train, self.val, self.test];
+        if ratios.iter().any(|r| !r.is_finite() || *r < 0.0) {
+            return Err("split ratios must be non-negative numbers".to_string());
+        }
+        let total: f64 = ratios.iter().sum();
+        if total <= 0.0 {
+            return Err("at least one split ratio must be positive".to_string());
+        }
+        Ok(ratios.map(|r| r / total))
+    }
+}
+
+// seed と画像IDから求める [0, 1) の値
+fn hash_fraction(seed: u64, image_id: Uuid) -> f64 {
+    let digest = Sha256::new()
+        .chain_update(seed.to_be_bytes())
+        .chain_update(image_id.as_bytes())
+        .finalize();
+    let value = u64::from_be_bytes(digest[..8].try_into().expect("digest has at least 8 bytes"));
+    (value >> 11) as f64 / (1u64 << 53) as f64
+}
+
+// 各画像を分割に振り分ける
+// images は (画像ID, その画像のラベル) の一覧
+pub fn assign_splits(
+    images: &[(Uuid, Vec<String>)],
+    options: &SplitOptions,
+    ratios: [f64; 3],
+) -> HashMap<Uuid, DatasetSplit> {
+    if options.stratify {
+        return assign_stratified(images, options.seed, ratios);
+    }
+
+    images
+        .iter()
+        .map(|(id, _)| {
+            let fraction = hash_fraction(options.seed, *id);
+            let split = if fraction < ratios[0] {
+                DatasetSplit::Train
+            } else if fraction < ratios[0] + ratios[1] {
+                DatasetSplit::Val
+            } else {
+                DatasetSplit::Test
+            };
+            (*id, split)
+        })
+        .collect()
+}
+
+// 画像を「最も少ないラベル」でグループ分けし、グループごとに比率どおりの枚数を振り分ける
+// アノテーションの無い画像は1つのグループとして扱う
+fn assign_stratified(
+    images: &[(Uuid, Vec<String>)],
+    seed: u64,
+    ratios: [f64; 3],
+) -> HashMap<Uuid, DatasetSplit> {
+    let mut label_counts: HashMap<&str, usize> = HashMap::new();
+    for (_, labels) in images {
+        for label in labels {
+            *label_counts.entry(label.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut groups: BTreeMap<Option<&str>, Vec<Uuid>> = BTreeMap::new();
+    for (id, labels) in images {
+        let rarest = labels
+            .iter()
+            .map(String::as_str)
+            .min_by_key(|label| (label_counts[label], *label));
+        groups.entry(rarest).or_default().push(*id);
+    }
+
+    let mut splits = HashMap::new();
+    for mut ids in groups.into_values() {
+        ids.sort_by(|a, b| hash_fraction(seed, *a).total_cmp(&hash_fraction(seed, *b)));
+
+        let counts = split_counts(ids.len(), ratios);
+        let mut remaining = ids.into_iter();
+        for (split, count) in DatasetSplit::ALL.into_iter().zip(counts) {
+            for id in remaining.by_ref().take(count) {
+                splits.insert(id, split);
+            }
+        }
+    }
+    splits
+}
+
+// n 枚を比率どおりに分ける枚数（最大剰余法）
+// 枚数が足りる限り、比率が正の分割には最低1枚を割り当てる
+fn split_counts(n: usize, ratios: [f64; 3]) -> [usize; 3] {
+    let exact = ratios.map(|r| r * n as f64);
+    let mut counts = exact.map(|e| e.floor() as usize);
+
+    let mut order = [0, 1, 2];
+    order.sort_by(|a, b| (exact[*b] - exact[*b].floor()).total_cmp(&(exact[*a] - exact[*a].floor())));
+    let assigned: usize = counts.iter().sum();
+    for i in order.into_iter().take(n - assigned) {
+        counts[i] += 1;
+    }
+
+    let positive = ratios.iter().filter(|r| **r > 0.0).count();
+    if n >= positive {
+        for i in 0..3 {
+            if ratios[i] > 0.0 && counts[i] == 0 {
+                let largest = (0..3).max_by_key(|j| counts[*j]).expect("three splits");
+                counts[largest] -= 1;
+                counts[i] += 1;
+            }
+        }
+    }
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn image(n: u128, labels: &[&str]) -> (Uuid, Vec<String>) {
+        (Uuid::from_u128(n), labels.iter().map(|label| label.to_string()).collect())
+    }
+
+    fn options(stratify: bool) -> SplitOptions {
+        SplitOptions {
+            train: 0.7,
+            val: 0.2,
+            test: 0.1,
+            seed: 42,
+            stratify,
+            resplit: false,
+        }
+    }
+
+    fn sample_images() -> Vec<(Uuid, Vec<String>)> {
+        (0..40)
+            .map(|n| match n % 10 {
+                0 => image(n, &["car", "bike"]),
+                1..=4 => image(n, &["person"]),
+                _ => image(n, &["car"]),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn splits_are_stable_across_calls_and_input_order() {
+        for stratify in [false, true] {
+            let options = options(stratify);
+            let ratios = options.ratios().unwrap();
+            let images = sample_images();
+            let mut reversed = images.clone();
+            reversed.reverse();
+
+            let first = assign_splits(&images, &options, ratios);
+            assert_eq!(first.len(), images.len());
+            assert_eq!(first, assign_splits(&images, &options, ratios));
+            assert_eq!(first, assign_splits(&reversed, &options, ratios));
+        }
+    }
+
+    #[test]
+    fn split_counts_sum_to_n() {
+        let ratio_sets = [[0.8, 0.2, 0.0], [0.7, 0.2, 0.1], [1.0 / 3.0; 3], [0.0, 0.0, 1.0], [0.05, 0.9, 0.05]];
+        for ratios in ratio_sets {
+            for n in 0..100 {
+                let counts = split_counts(n, ratios);
+                assert_eq!(counts.iter().sum::<usize>(), n, "n = {}, ratios = {:?}", n, ratios);
+                for i in 0..3 {
+                    if ratios[i] == 0.0 {
+                        assert_eq!(counts[i], 0, "n = {}, ratios = {:?}", n, ratios);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn each_stratum_lands_in_every_split() {
+        let options = options(true);
+        let images = sample_images();
+        let splits = assign_splits(&images, &options, options.ratios().unwrap());
+
+        // bike（4枚）・person（16枚）・car のみ（20枚）のどのグループも、すべての分割に含まれる
+        for label in ["bike", "person", "car"] {
+            let stratum: HashSet<DatasetSplit> = images
+                .iter()
+                .filter(|(_, labels)| match label {
+                    "car" => labels == &["car"],
+                    _ => labels.iter().any(|l| l == label),
+                })
+                .map(|(id, _)| splits[id])
+                .collect();
+            assert_eq!(stratum.len(), 3, "label {} is missing from a split", label);
+        }
+    }
+}
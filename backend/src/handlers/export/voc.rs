@@ -0,0 +1,121 @@
+use axum::http::StatusCode;
+
+use super::{export_file_name, write_zip_file, ArchiveWriter, ArchiveZip, ClassList, ImageData};
+use crate::models::DatasetSplit;
+
+// XMLのテキストとして埋め込めるようエスケープする
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+// 1画像分の Annotations/*.xml を作成する
+// 保存されている [x, y, w, h] を画像内に収まる (xmin, ymin, xmax, ymax) の整数座標に変換する
+fn annotation_xml(data: &ImageData, file_name: &str, classes: &ClassList, dataset_name: &str) -> String {
+    let width = data.image.width as f32;
+    let height = data.image.height as f32;
+
+    let mut xml = format!(
+        "<annotation>\n\
+         \t<folder>JPEGImages</folder>\n\
+         \t<filename>{}</filename>\n\
+         \t<source>\n\t\t<database>{}</database>\n\t</source>\n\
+         \t<size>\n\t\t<width>{}</width>\n\t\t<height>{}</height>\n\t\t<depth>3</depth>\n\t</size>\n\
+         \t<segmented>0</segmented>\n",
+        escape_xml(file_name),
+        escape_xml(dataset_name),
+        data.image.width,
+        data.image.height,
+    );
+
+    for ann in &data.annotations {
+        if classes.class_id(&ann.label).is_none() {
+            continue;
+        }
+        let Some([x, y, w, h]) = ann.bounding_box() else {
+            continue;
+        };
+
+        let xmin = x.clamp(0.0, width).round() as i64;
+        let ymin = y.clamp(0.0, height).round() as i64;
+        let xmax = (x + w).clamp(0.0, width).round() as i64;
+        let ymax = (y + h).clamp(0.0, height).round() as i64;
+        if xmax <= xmin || ymax <= ymin {
+            eprintln!(
+                "Annotation {} on image {} is outside the image or empty, skipping.",
+                ann.id, data.image.id
+            );
+            continue;
+        }
+
+        xml.push_str(&format!(
+            "\t<object>\n\
+             \t\t<name>{}</name>\n\
+             \t\t<pose>Unspecified</pose>\n\
+             \t\t<truncated>0</truncated>\n\
+             \t\t<difficult>0</difficult>\n\
+             \t\t<bndbox>\n\
+             \t\t\t<xmin>{}</xmin>\n\t\t\t<ymin>{}</ymin>\n\t\t\t<xmax>{}</xmax>\n\t\t\t<ymax>{}</ymax>\n\
+             \t\t</bndbox>\n\
+             \t</object>\n",
+            escape_xml(&ann.label),
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+        ));
+    }
+
+    xml.push_str("</annotation>\n");
+    xml
+}
+
+// Pascal VOC 形式
+// Annotations/*.xml、JPEGImages/*、ImageSets/Main/{train,val,test}.txt を格納する
+pub(super) struct VocWriter<'a> {
+    classes: &'a ClassList,
+    dataset_name: &'a str,
+    // 分割ごとの ImageSets に並べる画像名
+    image_sets: Vec<(DatasetSplit, String)>,
+}
+
+impl<'a> VocWriter<'a> {
+    pub(super) fn new(classes: &'a ClassList, dataset_name: &'a str, splits: &[DatasetSplit]) -> Self {
+        Self {
+            classes,
+            dataset_name,
+            image_sets: splits.iter().map(|split| (*split, String::new())).collect(),
+        }
+    }
+}
+
+impl ArchiveWriter for VocWriter<'_> {
+    fn add_image(&mut self, zip: &mut ArchiveZip, _index: usize, data: &ImageData) -> Result<(), StatusCode> {
+        let (base_name, ext) = export_file_name(&data.image);
+        let file_name = format!("{}{}", base_name, ext);
+
+        let xml_path = format!("{}/Annotations/{}.xml", self.dataset_name, base_name);
+        let xml = annotation_xml(data, &file_name, self.classes, self.dataset_name);
+        write_zip_file(zip, &xml_path, xml.as_bytes())?;
+
+        let image_path = format!("{}/JPEGImages/{}", self.dataset_name, file_name);
+        write_zip_file(zip, &image_path, &data.s3_data)?;
+
+        if let Some((_, ids)) = self.image_sets.iter_mut().find(|(split, _)| *split == data.split) {
+            ids.push_str(&base_name);
+            ids.push('\n');
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self, zip: &mut ArchiveZip) -> Result<(), StatusCode> {
+        for (split, ids) in &self.image_sets {
+            let path = format!("{}/ImageSets/Main/{}.txt", self.dataset_name, split.as_str());
+            write_zip_file(zip, &path, ids.as_bytes())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use bytes::Bytes;
+use std::{
+    io::{self, Read, Seek, SeekFrom, Write},
+    mem,
+    sync::{Arc, Mutex},
+};
+
+// zip の書き込み先
+// ZipWriter はファイルを書き終えるたびに seek で戻ってヘッダ（サイズ・CRC）を書き換えるため、
+// 書き込み中のファイルの分だけをメモリに保持する。set_flush_on_finish_file(true) の場合、
+// 書き終えたファイルは flush され以降書き換えられないので、take_flushed で取り出して送信できる
+#[derive(Clone, Default)]
+pub(super) struct ZipStream(Arc<Mutex<StreamState>>);
+
+#[derive(Default)]
+struct StreamState {
+    // 取り出し済みのバイト数（buf の先頭の、アーカイブ内での位置）
+    taken: u64,
+    buf: Vec<u8>,
+    // buf のうち flush 済みのバイト数
+    flushed: usize,
+    // buf 内の書き込み位置
+    pos: usize,
+}
+
+impl ZipStream {
+    fn state(&self) -> std::sync::MutexGuard<'_, StreamState> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // flush 済みで、以降書き換えられない部分を取り出す
+    pub(super) fn take_flushed(&self) -> Bytes {
+        let mut state = self.state();
+        let flushed = state.flushed;
+        let rest = state.buf.split_off(flushed);
+        let chunk = mem::replace(&mut state.buf, rest);
+        state.taken += flushed as u64;
+        state.pos -= flushed;
+        state.flushed = 0;
+        Bytes::from(chunk)
+    }
+
+    // ZipWriter::finish の後に、残りをすべて取り出す
+    pub(super) fn take_all(&self) -> Bytes {
+        let mut state = self.state();
+        state.taken += state.buf.len() as u64;
+        state.pos = 0;
+        state.flushed = 0;
+        Bytes::from(mem::take(&mut state.buf))
+    }
+}
+
+impl Write for ZipStream {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let mut state = self.state();
+        let start = state.pos;
+        let end = start + data.len();
+        if end > state.buf.len() {
+            state.buf.resize(end, 0);
+        }
+        state.buf[start..end].copy_from_slice(data);
+        state.pos = end;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = self.state();
+        state.flushed = state.buf.len();
+        Ok(())
+    }
+}
+
+impl Seek for ZipStream {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let mut state = self.state();
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(offset) => (state.taken + state.buf.len() as u64) as i128 + offset as i128,
+            SeekFrom::Current(offset) => (state.taken + state.pos as u64) as i128 + offset as i128,
+        };
+
+        let writable_from = (state.taken + state.flushed as u64) as i128;
+        if target < writable_from {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek into zip data that has already been flushed",
+            ));
+        }
+        state.pos = (target - state.taken as i128) as usize;
+        Ok(target as u64)
+    }
+}
+
+// ZipWriter::set_flush_on_finish_file が Read を要求するが、既存のファイルのコピーなどは行わない
+impl Read for ZipStream {
+    fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "zip stream is write-only"))
+    }
+}
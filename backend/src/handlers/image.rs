@@ -1,25 +1,35 @@
 use axum::{
-    extract::{State, Multipart, Path},
+    extract::{multipart::MultipartError, State, Multipart, Path, Query},
     http::StatusCode,
     response::Json,
-    response::Response,
-    body::Body,
+    response::{IntoResponse, Response},
+    body::{Body, Bytes},
 };
-use aws_sdk_s3::Client as S3Client;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use sqlx::{postgres::PgRow, FromRow, Row};
+use serde_json::{json, Value};
+use sqlx::{FromRow, Postgres, QueryBuilder};
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use reqwest;
-use image::GenericImageView;
+use image::{imageops::FilterType, DynamicImage, ImageFormat, ImageOutputFormat};
 use crate::{
-    models::{ImageResponse, ImageSearchRequest},
+    auth::policy::{Authorized, DeleteImages, Read, Upload},
+    models::{
+        DatasetStatus, DeleteImageOptions, DeleteImagesRequest, DeleteImagesResponse, ImageListResponse,
+        GetImageQuery, ImageMetadata, ImageRendition, ImageResponse, ImageSearchRequest, ImageSort, ImageSummary, ListImagesQuery, SortOrder,
+        UploadImageResult, UploadImagesResponse,
+    },
+    handlers::storage::MAX_SIGNED_UPLOAD_BYTES,
+    config::ImageDelivery,
+    storage::{validate_key, ByteRange, ObjectInfo, ObjectStore, SignedMethod, StorageError},
+    utils::exif,
     AppState,
 };
-use axum::http::header; // axumのheaderを使用
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::io::Cursor;
+use axum::http::{header, HeaderMap, HeaderValue}; // axumのheaderを使用
 use std::time::Duration;
-use aws_sdk_s3::presigning::PresigningConfig;
 
 // S3事前署名URL作成ハンドラ
 #[derive(Deserialize)]
@@ -35,212 +45,456 @@ pub struct PresignedUrlResponse {
 
 pub async fn generate_presigned_url (
     State(state): State<AppState>,
+    Authorized(user, _): Authorized<Upload>,
     Json(payload): Json<PresignedUrlRequest>,
-) -> Result<Json<PresignedUrlResponse>, StatusCode> {
-    let uuid = Uuid::new_v4();
-    let s3_key = format!("images/{}_{}", uuid, payload.filename);
-
-    let presigning_config = PresigningConfig::expires_in(Duration::from_secs(300))
-        .map_err(|e| {
-            eprintln!("Failed to create presigning config: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+) -> Result<Json<PresignedUrlResponse>, Response> {
+    // 登録できないキーの URL は発行しない（ストレージにオブジェクトだけが残るため）
+    let s3_key = format!("images/{}_{}", Uuid::new_v4(), payload.filename);
+    if !is_presigned_upload_key(&s3_key) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "filename must be a file name without path separators" })),
+        )
+            .into_response());
+    }
 
-        })?;
+    // 登録時に発行先のユーザーを確認するため、発行したキーを記録する
+    sqlx::query!(
+        "INSERT INTO image_uploads (s3_bucket, s3_key, user_id) VALUES ($1, $2, $3)",
+        state.store.bucket(),
+        s3_key,
+        user.id
+    )
+    .execute(&state.db)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to record presigned upload: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
 
-    let presigned_request = state.s3_client
-        .put_object()
-        .bucket(&state.s3_bucket)
-        .key(&s3_key)
-        .presigned(presigning_config)
+    let url = state.store
+        .signed_url(SignedMethod::Put, &s3_key, Duration::from_secs(300))
         .await
         .map_err(|e| {
             eprintln!("Failed to generate presigned URL: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
         })?;
 
     Ok(Json(PresignedUrlResponse {
-        url: presigned_request.uri().to_string(),
+        url,
         s3_key,
     }))
 }
 
-// 画像アップロードハンドラ
+// 事前署名URLでアップロードした画像の登録ハンドラ
+// 大きさ・形式はクライアントの申告を使わず、保存されたオブジェクトから読み取る
 #[derive(Deserialize)]
 pub struct RegisterImageRequest {
     s3_key: String,
     original_filename: String,
+}
+
+#[derive(Serialize)]
+pub struct RegisterImageResponse {
+    id: Uuid,
     file_size: i64,
     width: i32,
     height: i32,
     format: String,
+    content_hash: String,
+    metadata: ImageMetadata,
 }
 
-#[derive(Serialize)]
-pub struct RegisterImageResponse {
-    id: Uuid,
+// 受け付ける画像の形式
+const ACCEPTED_FORMATS: [ImageFormat; 4] = [ImageFormat::Jpeg, ImageFormat::Png, ImageFormat::WebP, ImageFormat::Tiff];
+const UNSUPPORTED_IMAGE: &str = "unsupported image format; use JPEG, PNG, WebP or TIFF";
+
+// 画像全体はデコードせず、ヘッダーから形式と大きさだけを読み取る
+fn probe_image(data: &[u8]) -> Option<(ImageFormat, u32, u32)> {
+    let reader = image::io::Reader::new(Cursor::new(data)).with_guessed_format().ok()?;
+    let format = reader.format().filter(|format| ACCEPTED_FORMATS.contains(format))?;
+    let (width, height) = reader.into_dimensions().ok()?;
+    Some((format, width, height))
+}
+
+// BAKE_IMAGE_ORIENTATION で JPEG を再エンコードする際の品質
+const BAKED_JPEG_QUALITY: u8 = 95;
+
+// 取り込む画像（bake した場合は向きを反映した後の内容）
+struct IngestedImage {
+    data: Bytes,
+    format: ImageFormat,
+    width: u32,
+    height: u32,
+    metadata: ImageMetadata,
+}
+
+// 形式を確認し、EXIF のメタデータと向きを反映した（表示される）幅・高さを読み取る
+// bake の場合は向きを画素に反映して再エンコードする（WebP はエンコードできないため元のまま）
+fn ingest_image(data: Bytes, bake: bool) -> Result<IngestedImage, &'static str> {
+    let (format, mut width, mut height) = probe_image(&data).ok_or(UNSUPPORTED_IMAGE)?;
+    let mut metadata = exif::read_metadata(&data);
+    let orientation = metadata.orientation.unwrap_or(1);
+    if exif::swaps_dimensions(orientation) {
+        std::mem::swap(&mut width, &mut height);
+    }
+
+    if !bake || orientation == 1 || format == ImageFormat::WebP {
+        return Ok(IngestedImage { data, format, width, height, metadata });
+    }
+
+    let image = image::load_from_memory_with_format(&data, format).map_err(|_| "image could not be decoded")?;
+    let image = exif::apply_orientation(image, orientation);
+    let output = match format {
+        ImageFormat::Jpeg => ImageOutputFormat::Jpeg(BAKED_JPEG_QUALITY),
+        format => format.into(),
+    };
+    let mut baked = Cursor::new(Vec::new());
+    image.write_to(&mut baked, output).map_err(|_| "image could not be re-encoded")?;
+    metadata.orientation_applied = true;
+
+    Ok(IngestedImage {
+        data: Bytes::from(baked.into_inner()),
+        format,
+        width: image.width(),
+        height: image.height(),
+        metadata,
+    })
+}
+
+// generate_presigned_url が発行する形式（images/{uuid}_{filename}、filename はディレクトリを含まない）のキーか
+fn is_presigned_upload_key(key: &str) -> bool {
+    if validate_key(key).is_err() {
+        return false;
+    }
+    key.strip_prefix("images/")
+        .and_then(|rest| rest.split_once('_'))
+        .is_some_and(|(uuid, filename)| {
+            Uuid::parse_str(uuid).is_ok() && !filename.is_empty() && !filename.contains(['/', '\\'])
+        })
 }
 
 pub async fn register_uploaded_image(
     State(state): State<AppState>,
+    Authorized(user, _): Authorized<Upload>,
     Json(payload): Json<RegisterImageRequest>,
-) -> Result<Json<RegisterImageResponse>, StatusCode> {
+) -> Result<Json<RegisterImageResponse>, Response> {
+    let bad_request = |message: &str| {
+        (StatusCode::BAD_REQUEST, Json(json!({ "error": message, "s3_key": payload.s3_key }))).into_response()
+    };
+    let conflict = || {
+        (
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "image is already registered", "s3_key": payload.s3_key })),
+        )
+            .into_response()
+    };
+
+    if !is_presigned_upload_key(&payload.s3_key) {
+        return Err(bad_request("s3_key was not issued for image upload"));
+    }
+
+    let registered = sqlx::query_scalar!(
+        "SELECT EXISTS(SELECT 1 FROM images WHERE s3_bucket = $1 AND s3_key = $2)",
+        state.store.bucket(),
+        payload.s3_key
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to check registered image: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?
+    .unwrap_or(false);
+    if registered {
+        return Err(conflict());
+    }
+
+    // 他のユーザーに発行されたキーは登録できない
+    let issued_to = sqlx::query_scalar!(
+        "SELECT user_id FROM image_uploads WHERE s3_bucket = $1 AND s3_key = $2",
+        state.store.bucket(),
+        payload.s3_key
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to fetch presigned upload: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+    match issued_to {
+        None => return Err(bad_request("s3_key was not issued for image upload")),
+        Some(user_id) if user_id != user.id => {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(json!({ "error": "s3_key was issued to another user", "s3_key": payload.s3_key })),
+            )
+                .into_response());
+        }
+        Some(_) => {}
+    }
+
+    let object = match state.store.head(&payload.s3_key).await {
+        Ok(object) => object,
+        Err(StorageError::NotFound(_)) => return Err(bad_request("object has not been uploaded")),
+        Err(e) => {
+            eprintln!("Failed to stat uploaded object {}: {}", payload.s3_key, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    if object.size > MAX_SIGNED_UPLOAD_BYTES as i64 {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            Json(json!({ "error": "image is too large", "max_bytes": MAX_SIGNED_UPLOAD_BYTES })),
+        )
+            .into_response());
+    }
+
+    let data = state.store.get(&payload.s3_key).await.map_err(|e| {
+        eprintln!("Failed to read uploaded object {}: {}", payload.s3_key, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    let image = ingest_image(data, state.config.bake_image_orientation).map_err(bad_request)?;
+
     let id = Uuid::new_v4();
-    let user_id: Uuid = sqlx::query_scalar("SELECT id FROM users LIMIT 1")
-        .fetch_one(&state.db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let file_size = image.data.len() as i64;
+    let format = image.format.to_mime_type().to_string();
+    let content_hash = hex::encode(Sha256::digest(&image.data));
+
+    // 向きを反映した画素でアップロードされたオブジェクトを置き換える
+    if image.metadata.orientation_applied {
+        state.store.put(&payload.s3_key, image.data, &format).await.map_err(|e| {
+            eprintln!("Failed to store oriented image {}: {}", payload.s3_key, e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+    }
+
+    let internal_error = |context: &str, e: sqlx::Error| {
+        eprintln!("{}: {}", context, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+    let mut tx = state.db.begin().await.map_err(|e| internal_error("Failed to begin transaction", e))?;
 
     sqlx::query!(
         r#"
         INSERT INTO images 
             (id, user_id, s3_bucket, s3_key, width, height, format, 
-            original_filename, filename, file_size, created_at, updated_at)
+            original_filename, filename, file_size, content_hash, metadata, created_at, updated_at)
         VALUES 
-            ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW(), NOW())
+            ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, NOW(), NOW())
         "#,
         id,
-        user_id,
-        &state.s3_bucket,
+        user.id,
+        state.store.bucket(),
         payload.s3_key,
-        payload.width,
-        payload.height,
-        payload.format,
+        image.width as i32,
+        image.height as i32,
+        format,
         payload.original_filename,
         payload.original_filename, // filename にも同じ値を使用
-        payload.file_size
+        file_size,
+        content_hash,
+        json!(image.metadata)
     )
-    .execute(&state.db)
+    .execute(&mut *tx)
     .await
-    .map_err(|e| {
-        eprintln!("Failed to register image in DB: {}", e);
-        StatusCode::INTERNAL_SERVER_ERROR
+    .map_err(|e| match e {
+        // 同じキーの登録が同時に行われた場合
+        sqlx::Error::Database(db) if db.is_unique_violation() => conflict(),
+        e => internal_error("Failed to register image in DB", e),
     })?;
 
-    Ok(Json(RegisterImageResponse { id }))
+    sqlx::query!(
+        "DELETE FROM image_uploads WHERE s3_bucket = $1 AND s3_key = $2",
+        state.store.bucket(),
+        payload.s3_key
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| internal_error("Failed to delete presigned upload", e))?;
+
+    tx.commit().await.map_err(|e| internal_error("Failed to commit transaction", e))?;
+
+    Ok(Json(RegisterImageResponse {
+        id,
+        file_size,
+        width: image.width as i32,
+        height: image.height as i32,
+        format,
+        content_hash,
+        metadata: image.metadata,
+    }))
 }
 
 
     
-// upload_image ハンドラ
+// multipart で直接アップロードされた画像の保存ハンドラ
+// "image" フィールドを複数含めることができ、ファイルごとに結果を返す
 pub async fn upload_image(
     State(state): State<AppState>,
+    Authorized(user, _): Authorized<Upload>,
     mut multipart: Multipart,
-) -> Result<Json<ImageResponse>, StatusCode> {
-    while let Some(field) = multipart.next_field().await.map_err(|_| StatusCode::BAD_REQUEST)? {
-        if field.name() == Some("image") {
-            let filename = field.file_name().unwrap_or("unknown").to_string();
-            let data = field.bytes().await.map_err(|_| StatusCode::BAD_REQUEST)?;
-
-            let image_format = image::guess_format(&data)
-                .map_err(|_| StatusCode::BAD_REQUEST)?;
-
-            let uuid = Uuid::new_v4();
-            let s3_key = format!("images/{}_{}", uuid, &filename);
-
-            // AIサービスで画像をベクトル化
-            let client = reqwest::Client::new();
-            let ai_service_url = "http://localhost:8001";
-            
-            // バイトデータをVec<u8>に変換
-            let data_vec = data.to_vec();
-            
-            // multipart/form-dataフォームを作成
-            let part = reqwest::multipart::Part::bytes(data_vec)
-                .file_name(filename.clone())
-                .mime_str(&image_format.to_mime_type())
-                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-            
-            let form = reqwest::multipart::Form::new().part("image", part);
-
-            let vectorize_res = client
-                .post(format!("{}/vectorize_image", ai_service_url))
-                .multipart(form)
-                .send()
-                .await;
-
-            let image_vector_value: Option<Value> = match vectorize_res {
-                Ok(res) if res.status().is_success() => res.json::<Value>().await.ok(),
-                _ => None,
-            };
-            
-            let image_vector: Option<Vec<f32>> = image_vector_value
-                .and_then(|val| val.get("vector").cloned())
-                .and_then(|vec_val| serde_json::from_value(vec_val).ok());
-
-
-            let mut transaction = state.db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-            let s3_upload_result = state.s3_client
-                .put_object()
-                .bucket(&state.s3_bucket)
-                .key(&s3_key)
-                .body(data.clone().into())
-                .content_type(image_format.to_mime_type())
-                .send()
-                .await;
-            
-            if let Err(e) = s3_upload_result {
-                eprintln!("S3 upload failed: {}", e);
-                transaction.rollback().await.ok();
-                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+) -> Result<Json<UploadImagesResponse>, Response> {
+    let client = reqwest::Client::new();
+    let mut results = Vec::new();
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) if results.is_empty() => return Err(multipart_error(e)),
+            // 保存済みのファイルの結果は返し、読めなかった以降のファイルは失敗として扱う
+            Err(e) => {
+                results.push(UploadImageResult::failed(String::new(), multipart_error_message(&e)));
+                break;
             }
-            
-            let image_obj = image::load_from_memory(&data).map_err(|_| StatusCode::BAD_REQUEST)?;
-            let (width, height) = image_obj.dimensions();
-            let created_at = Utc::now();
-
-            // TODO: 認証機能が実装されるまで、仮のユーザーIDを使用
-            // データベースから最初のユーザーのIDを取得
-            let user_id: Uuid = sqlx::query_scalar("SELECT id FROM users LIMIT 1")
-                .fetch_one(&state.db)
-                .await
-                .map_err(|e| {
-                    eprintln!("Failed to fetch a user from the database. Is it seeded? Error: {}", e);
-                    StatusCode::INTERNAL_SERVER_ERROR
-                })?;
-
-            let new_image_id: Uuid = sqlx::query(
-                r#"
-                INSERT INTO images (id, user_id, s3_bucket, s3_key, width, height, format, classification_label, created_at, vector, filename, original_filename, file_size)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
-                RETURNING id
-                "#,
-            )
-            .bind(uuid)
-            .bind(user_id)
-            .bind(&state.s3_bucket)
-            .bind(&s3_key)
-            .bind(width as i32)
-            .bind(height as i32)
-            .bind(image_format.to_mime_type().to_string())
-            .bind(None::<String>)
-            .bind(created_at)
-            .bind(image_vector.map(serde_json::to_value).transpose().map_err(|e| {
-                eprintln!("Failed to serialize vector: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?)
-            .bind(&filename) // $11
-            .bind(&filename) // $12 original_filename
-            .bind(data.len() as i64) // $13 file_size
-            .map(|row: PgRow| row.get("id"))
-            .fetch_one(&mut *transaction)
-            .await
-            .map_err(|e| {
-                eprintln!("DB insert failed: {}", e);
-                StatusCode::INTERNAL_SERVER_ERROR
-            })?;
-
-            transaction.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-            return Ok(Json(ImageResponse {
-                id: new_image_id,
-                s3_key,
-                created_at,
-            }));
+        };
+        if field.name() != Some("image") {
+            continue;
         }
+
+        let filename = field.file_name().unwrap_or("unknown").to_string();
+        let data = match field.bytes().await {
+            Ok(data) => data,
+            Err(e) if results.is_empty() => return Err(multipart_error(e)),
+            Err(e) => {
+                results.push(UploadImageResult::failed(filename, multipart_error_message(&e)));
+                break;
+            }
+        };
+
+        let result = match store_uploaded_image(&state, &client, user.id, &filename, data).await {
+            Ok(image) => UploadImageResult { filename, image: Some(image), error: None },
+            Err(error) => UploadImageResult::failed(filename, error),
+        };
+        results.push(result);
+    }
+
+    if results.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, Json(json!({ "error": "no image field in the request" }))).into_response());
     }
 
-    Err(StatusCode::BAD_REQUEST)
+    Ok(Json(UploadImagesResponse { results }))
+}
+
+impl UploadImageResult {
+    fn failed(filename: String, error: impl Into<String>) -> Self {
+        Self { filename, image: None, error: Some(error.into()) }
+    }
+}
+
+fn multipart_error(e: MultipartError) -> Response {
+    (e.status(), Json(json!({ "error": multipart_error_message(&e) }))).into_response()
+}
+
+fn multipart_error_message(e: &MultipartError) -> String {
+    if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        "request body exceeds the upload size limit (MAX_UPLOAD_BYTES)".to_string()
+    } else {
+        e.body_text()
+    }
+}
+
+// 1ファイル分の保存
+// register_uploaded_image と同じく、ストレージに保存してから DB に登録する（登録に失敗した場合はオブジェクトを消す）
+async fn store_uploaded_image(
+    state: &AppState,
+    client: &reqwest::Client,
+    user_id: Uuid,
+    filename: &str,
+    data: Bytes,
+) -> Result<ImageResponse, String> {
+    let image = ingest_image(data, state.config.bake_image_orientation)?;
+
+    let id = Uuid::new_v4();
+    // ディレクトリ部分はキーに含めない
+    let filename = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    let s3_key = format!("images/{}_{}", id, filename);
+    if validate_key(&s3_key).is_err() {
+        return Err("invalid filename".to_string());
+    }
+
+    // AIサービスで画像をベクトル化（失敗してもベクトルなしで保存する）
+    let part = reqwest::multipart::Part::bytes(image.data.to_vec())
+        .file_name(filename.to_string())
+        .mime_str(image.format.to_mime_type())
+        .map_err(|e| e.to_string())?;
+    let form = reqwest::multipart::Form::new().part("image", part);
+
+    let vectorize_res = client
+        .post(state.config.ai_service_endpoint("vectorize_image"))
+        .multipart(form)
+        .send()
+        .await;
+
+    let image_vector_value: Option<Value> = match vectorize_res {
+        Ok(res) if res.status().is_success() => res.json::<Value>().await.ok(),
+        _ => None,
+    };
+    let image_vector: Option<Value> = image_vector_value
+        .and_then(|val| val.get("vector").cloned())
+        .and_then(|vec_val| serde_json::from_value::<Vec<f32>>(vec_val).ok())
+        .map(|vector| json!(vector));
+
+    let file_size = image.data.len() as i64;
+    let format = image.format.to_mime_type().to_string();
+    let content_hash = hex::encode(Sha256::digest(&image.data));
+
+    if let Err(e) = state.store.put(&s3_key, image.data, &format).await {
+        eprintln!("Failed to store uploaded image {}: {}", s3_key, e);
+        return Err("failed to store image".to_string());
+    }
+
+    let inserted = sqlx::query_scalar!(
+        r#"
+        INSERT INTO images
+            (id, user_id, s3_bucket, s3_key, width, height, format, vector,
+            original_filename, filename, file_size, content_hash, metadata, created_at, updated_at)
+        VALUES
+            ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, NOW(), NOW())
+        RETURNING created_at as "created_at!"
+        "#,
+        id,
+        user_id,
+        state.store.bucket(),
+        s3_key,
+        image.width as i32,
+        image.height as i32,
+        format,
+        image_vector,
+        filename,
+        filename, // filename にも同じ値を使用
+        file_size,
+        content_hash,
+        json!(image.metadata)
+    )
+    .fetch_one(&state.db)
+    .await;
+
+    let created_at = match inserted {
+        Ok(created_at) => created_at,
+        Err(e) => {
+            eprintln!("Failed to register uploaded image {}: {}", s3_key, e);
+            if let Err(e) = state.store.delete(&s3_key).await {
+                eprintln!("Failed to delete object {} of an unregistered image: {}", s3_key, e);
+            }
+            return Err("failed to register image".to_string());
+        }
+    };
+
+    Ok(ImageResponse {
+        id,
+        s3_key,
+        filename: filename.to_string(),
+        file_size,
+        width: image.width as i32,
+        height: image.height as i32,
+        format,
+        content_hash,
+        metadata: image.metadata,
+        created_at,
+    })
 }
 
 
@@ -286,11 +540,12 @@ pub struct SearchResultWithSimilarity {
 
 pub async fn search_images(
     State(state): State<AppState>,
+    _: Authorized<Read>,
     Json(payload): Json<ImageSearchRequest>,
 ) -> Result<Json<Vec<SearchResultWithSimilarity>>, StatusCode> {
     // 1. DBから全画像ベクトルを取得
     let image_vectors = sqlx::query_as::<_, ImageVector>(
-        "SELECT id, vector FROM images WHERE vector IS NOT NULL"
+        "SELECT id, vector FROM images WHERE vector IS NOT NULL AND deleted_at IS NULL"
     )
     .fetch_all(&state.db)
     .await
@@ -305,13 +560,11 @@ pub async fn search_images(
 
     // 2. 検索テキストをベクトル化
     let client = reqwest::Client::new();
-    let ai_service_url = "http://localhost:8001";
-    
     // リクエストの形式を修正
     let vectorize_req = vec![payload.query];  // 直接文字列の配列を送信
 
     let vectorize_res = client
-        .post(format!("{}/vectorize_text", ai_service_url))
+        .post(state.config.ai_service_endpoint("vectorize_text"))
         .json(&vectorize_req)  // VectorizeTextRequestではなく、直接配列を送信
         .send()
         .await
@@ -344,7 +597,7 @@ pub async fn search_images(
     };
 
     let search_res = client
-        .post(format!("{}/search_similar_images", ai_service_url))
+        .post(state.config.ai_service_endpoint("search_similar_images"))
         .json(&search_req)
         .send()
         .await
@@ -370,54 +623,863 @@ pub async fn search_images(
     Ok(Json(result_items))
 }
 
+// --- list_images ハンドラ ---
+
+const DEFAULT_IMAGES_PER_PAGE: i64 = 50;
+const MAX_IMAGES_PER_PAGE: i64 = 200;
+
+impl ImageSort {
+    // 並べ替えに使うカラムと、カーソルの値（文字列）をキャストする型
+    fn column(self) -> (&'static str, &'static str) {
+        match self {
+            ImageSort::CreatedAt => ("created_at", "TIMESTAMPTZ"),
+            ImageSort::Filename => ("original_filename", "VARCHAR"),
+            ImageSort::FileSize => ("file_size", "BIGINT"),
+        }
+    }
+
+    fn key(self, image: &ImageSummary) -> String {
+        match self {
+            ImageSort::CreatedAt => image.created_at.to_rfc3339(),
+            ImageSort::Filename => image.original_filename.clone(),
+            ImageSort::FileSize => image.file_size.to_string(),
+        }
+    }
+
+    fn is_valid_key(self, key: &str) -> bool {
+        match self {
+            ImageSort::CreatedAt => DateTime::parse_from_rfc3339(key).is_ok(),
+            ImageSort::Filename => true,
+            ImageSort::FileSize => key.parse::<i64>().is_ok(),
+        }
+    }
+}
+
+// 前のページの最後の画像の位置（並べ替えキーとID）
+// クライアントには JSON を16進数にした不透明な文字列として渡す
+#[derive(Serialize, Deserialize)]
+struct ImageCursor {
+    sort: ImageSort,
+    order: SortOrder,
+    key: String,
+    id: Uuid,
+}
+
+impl ImageCursor {
+    fn encode(&self) -> String {
+        hex::encode(serde_json::to_vec(self).expect("cursor is serializable"))
+    }
+
+    // 壊れたカーソルや、並べ替えの条件が異なるカーソルは None
+    fn decode(cursor: &str, sort: ImageSort, order: SortOrder) -> Option<Self> {
+        let cursor: Self = serde_json::from_slice(&hex::decode(cursor).ok()?).ok()?;
+        (cursor.sort == sort && cursor.order == order && sort.is_valid_key(&cursor.key)).then_some(cursor)
+    }
+}
+
+// 画像一覧（メタデータのみ、vector は含まない）
+pub async fn list_images(
+    State(state): State<AppState>,
+    _: Authorized<Read>,
+    Query(query): Query<ListImagesQuery>,
+) -> Result<Json<ImageListResponse>, Response> {
+    let limit = query.limit.unwrap_or(DEFAULT_IMAGES_PER_PAGE).clamp(1, MAX_IMAGES_PER_PAGE);
+    let cursor = match &query.cursor {
+        Some(cursor) => Some(ImageCursor::decode(cursor, query.sort, query.order).ok_or_else(|| {
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "invalid cursor for this sort order" })),
+            )
+                .into_response()
+        })?),
+        None => None,
+    };
+
+    let mut builder: QueryBuilder<Postgres> = QueryBuilder::new(
+        r#"
+        SELECT
+            id, user_id, filename, original_filename, s3_bucket, s3_key, file_size,
+            width, height, format, classification_label, created_at, deleted_at, metadata
+        FROM images
+        "#,
+    );
+
+    if query.deleted {
+        // 復元期間が過ぎた（purge 待ちの）画像は restore_image で復元できないため含めない
+        builder
+            .push(" WHERE deleted_at >= NOW() - make_interval(secs => ")
+            .push_bind(state.config.image_restore_window_secs as f64)
+            .push(")");
+    } else {
+        builder.push(" WHERE deleted_at IS NULL");
+    }
+
+    if let Some(user_id) = query.user_id {
+        builder.push(" AND user_id = ").push_bind(user_id);
+    }
+    if let Some(created_after) = query.created_after {
+        builder.push(" AND created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = query.created_before {
+        builder.push(" AND created_at < ").push_bind(created_before);
+    }
+    if let Some(format) = &query.format {
+        builder.push(" AND format = ").push_bind(format);
+    }
+    if let Some(classification_label) = &query.classification_label {
+        builder.push(" AND classification_label = ").push_bind(classification_label);
+    }
+    if let Some(dataset_id) = query.dataset_id {
+        builder
+            .push(" AND EXISTS(SELECT 1 FROM dataset_images di WHERE di.image_id = images.id AND di.dataset_id = ")
+            .push_bind(dataset_id)
+            .push(")");
+    }
+    match query.annotated {
+        Some(true) => {
+            builder.push(" AND EXISTS(SELECT 1 FROM annotations a WHERE a.image_id = images.id)");
+        }
+        Some(false) => {
+            builder.push(" AND NOT EXISTS(SELECT 1 FROM annotations a WHERE a.image_id = images.id)");
+        }
+        None => {}
+    }
+    if let Some(label) = &query.label {
+        builder
+            .push(" AND EXISTS(SELECT 1 FROM annotations a WHERE a.image_id = images.id AND a.label = ")
+            .push_bind(label)
+            .push(")");
+    }
+
+    let (column, cast) = query.sort.column();
+    let (direction, comparison) = match query.order {
+        SortOrder::Asc => ("ASC", ">"),
+        SortOrder::Desc => ("DESC", "<"),
+    };
+    if let Some(cursor) = cursor {
+        builder
+            .push(format!(" AND ({}, id) {} (", column, comparison))
+            .push_bind(cursor.key)
+            .push(format!("::{}, ", cast))
+            .push_bind(cursor.id)
+            .push(")");
+    }
+    // 次のページの有無を判定するため1件多く取得する
+    builder
+        .push(format!(" ORDER BY {0} {1}, id {1} LIMIT ", column, direction))
+        .push_bind(limit + 1);
+
+    let mut images: Vec<ImageSummary> = builder
+        .build_query_as()
+        .fetch_all(&state.db)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to list images: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+
+    let next_cursor = if images.len() as i64 > limit {
+        images.truncate(limit as usize);
+        images.last().map(|image| {
+            ImageCursor {
+                sort: query.sort,
+                order: query.order,
+                key: query.sort.key(image),
+                id: image.id,
+            }
+            .encode()
+        })
+    } else {
+        None
+    };
+
+    Ok(Json(ImageListResponse { images, next_cursor }))
+}
+
 // 画像取得ハンドラを追加
+// size=thumb・preview の場合はリサイズ版を返す（元画像がそれより小さい場合は元画像）
+// IMAGE_DELIVERY=redirect の場合は期限付きURLへリダイレクトし、proxy の場合は ETag・Range に対応して読み出しながら返す
 pub async fn get_image(
     State(state): State<AppState>,
+    _: Authorized<Read>,
     Path(image_id): Path<Uuid>,
+    Query(query): Query<GetImageQuery>,
+    headers: HeaderMap,
 ) -> Result<Response<Body>, StatusCode> {
     // 1. データベースから画像情報を取得
     let image = sqlx::query!(
         r#"
-        SELECT s3_bucket, s3_key, format
+        SELECT s3_key, format, width, height, metadata, content_hash
         FROM images
-        WHERE id = $1
+        WHERE id = $1 AND deleted_at IS NULL
         "#,
         image_id
     )
-    .fetch_one(&state.db)
+    .fetch_optional(&state.db)
     .await
     .map_err(|e| {
         eprintln!("Failed to fetch image info: {}", e);
-        StatusCode::NOT_FOUND
-    })?;
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?
+    .ok_or(StatusCode::NOT_FOUND)?;
 
-    // 2. S3から画像データを取得
-    let get_object_output = state.s3_client
-        .get_object()
-        .bucket(&image.s3_bucket)
-        .key(&image.s3_key)
-        .send()
+    // 2. 返すオブジェクトを決める（リサイズ版は無ければ作る）
+    // 同じキーのオブジェクトは書き換えないため、ETag は内容のハッシュ（無ければ画像ID）から決める
+    let version = image.content_hash.unwrap_or_else(|| image_id.to_string());
+    let (object, content_type, etag) = match query.size.max_side() {
+        Some(max_side) if image.width.max(image.height) as u32 > max_side => {
+            let format = rendition_format(&image.format);
+            let metadata = image
+                .metadata
+                .and_then(|metadata| serde_json::from_value(metadata).ok())
+                .unwrap_or_default();
+            let object = ensure_rendition(&state, image_id, &image.s3_key, metadata, query.size, format).await?;
+            let etag = format!("\"{}-{}\"", version, query.size.name());
+            (object, format.to_mime_type().to_string(), etag)
+        }
+        _ => {
+            let object = state.store
+                .head(&image.s3_key)
+                .await
+                .map_err(|e| storage_error_status(&image.s3_key, e))?;
+            (object, image.format, format!("\"{}\"", version))
+        }
+    };
+
+    if state.config.image_delivery == ImageDelivery::Redirect {
+        let url = state.store
+            .signed_url(
+                SignedMethod::Get,
+                &object.key,
+                Duration::from_secs(state.config.image_url_expires_secs),
+            )
+            .await
+            .map_err(|e| storage_error_status(&object.key, e))?;
+
+        return Response::builder()
+            .status(StatusCode::FOUND)
+            .header(header::LOCATION, url)
+            // URLの期限が切れるため、リダイレクト自体はキャッシュさせない
+            .header(header::CACHE_CONTROL, "no-store")
+            .body(Body::empty())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    // 3. レスポンスを構築
+    // axumのResponseBuilderを使用
+    let response = Response::builder()
+        .header(header::CACHE_CONTROL, IMAGE_CACHE_CONTROL)
+        .header(header::ETAG, &etag)
+        .header(header::ACCEPT_RANGES, "bytes")
+        // リサイズ版の座標を元画像の座標系に戻すための大きさ
+        .header(IMAGE_WIDTH_HEADER, image.width)
+        .header(IMAGE_HEIGHT_HEADER, image.height);
+
+    if etag_matches(headers.get(header::IF_NONE_MATCH), &etag) {
+        return response
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    let size = object.size as u64;
+    let range = requested_range(&headers, &etag, size);
+
+    let response = response.header(header::CONTENT_TYPE, content_type);
+    let (response, range) = match range {
+        RangeRequest::Full => (response.header(header::CONTENT_LENGTH, size), None),
+        RangeRequest::Partial(range) => (
+            response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", range.start, range.end, size))
+                .header(header::CONTENT_LENGTH, range.end - range.start + 1),
+            Some(range),
+        ),
+        RangeRequest::Unsatisfiable => {
+            return response
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+                .body(Body::empty())
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    // 4. ストレージから読み出しながら返す
+    let stream = state.store
+        .get_stream(&object.key, range)
+        .await
+        .map_err(|e| storage_error_status(&object.key, e))?;
+
+    response
+        .body(Body::from_stream(stream))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+// ブラウザには1日キャッシュさせ、その後は ETag で再検証させる（認証が必要なため共有キャッシュには載せない）
+const IMAGE_CACHE_CONTROL: &str = "private, max-age=86400";
+
+// 行があるのにオブジェクトが無い場合は 404、ストレージ自体の障害は 502
+fn storage_error_status(key: &str, e: StorageError) -> StatusCode {
+    eprintln!("Failed to read image object {}: {}", key, e);
+    match e {
+        StorageError::NotFound(_) => StatusCode::NOT_FOUND,
+        StorageError::InvalidKey(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        StorageError::Backend(_) => StatusCode::BAD_GATEWAY,
+    }
+}
+
+// If-None-Match に ETag（弱い比較）または * が含まれるか
+fn etag_matches(if_none_match: Option<&HeaderValue>, etag: &str) -> bool {
+    let Some(value) = if_none_match.and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+    value
+        .split(',')
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RangeRequest {
+    Full,
+    Partial(ByteRange),
+    Unsatisfiable,
+}
+
+// If-Range が現在の ETag と異なる場合は、Range を無視して全体を返す
+fn requested_range(headers: &HeaderMap, etag: &str, size: u64) -> RangeRequest {
+    match headers.get(header::RANGE) {
+        Some(range) if headers.get(header::IF_RANGE).is_none_or(|if_range| if_range == etag) => {
+            parse_range(range, size)
+        }
+        _ => RangeRequest::Full,
+    }
+}
+
+// Range ヘッダー（bytes=start-end・start-・-suffix の1範囲のみ）を解釈する
+// 複数範囲や解釈できない値の場合は全体を返す
+fn parse_range(value: &HeaderValue, size: u64) -> RangeRequest {
+    let Some(spec) = value.to_str().ok().and_then(|value| value.trim().strip_prefix("bytes=")) else {
+        return RangeRequest::Full;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Full;
+    }
+    let Some((start, end)) = spec.split_once('-') else {
+        return RangeRequest::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        // 末尾の suffix バイト
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return RangeRequest::Unsatisfiable,
+            Ok(suffix) => (size.saturating_sub(suffix), size.saturating_sub(1)),
+            Err(_) => return RangeRequest::Full,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, size.saturating_sub(1)),
+            Err(_) => return RangeRequest::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(size.saturating_sub(1))),
+            _ => return RangeRequest::Full,
+        },
+    };
+
+    if size == 0 || start >= size {
+        return RangeRequest::Unsatisfiable;
+    }
+    RangeRequest::Partial(ByteRange { start, end })
+}
+
+
+// --- リサイズ版（サムネイル・プレビュー） ---
+
+pub const IMAGE_WIDTH_HEADER: &str = "x-image-width";
+pub const IMAGE_HEIGHT_HEADER: &str = "x-image-height";
+
+const RENDITIONS: [ImageRendition; 2] = [ImageRendition::Thumb, ImageRendition::Preview];
+const RENDITION_JPEG_QUALITY: u8 = 85;
+
+impl ImageRendition {
+    // 長辺の最大ピクセル数（Original は元画像のまま）
+    fn max_side(self) -> Option<u32> {
+        match self {
+            ImageRendition::Original => None,
+            ImageRendition::Thumb => Some(256),
+            ImageRendition::Preview => Some(1600),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ImageRendition::Original => "original",
+            ImageRendition::Thumb => "thumb",
+            ImageRendition::Preview => "preview",
+        }
+    }
+}
+
+// リサイズ版の形式（JPEG 以外は透過を保つため PNG）
+fn rendition_format(original_format: &str) -> ImageFormat {
+    if original_format == ImageFormat::Jpeg.to_mime_type() {
+        ImageFormat::Jpeg
+    } else {
+        ImageFormat::Png
+    }
+}
+
+const RENDITION_PREFIX: &str = "renditions/";
+
+fn rendition_key(image_id: Uuid, rendition: ImageRendition, format: ImageFormat) -> String {
+    format!("{}{}/{}.{}", RENDITION_PREFIX, image_id, rendition.name(), format.extensions_str()[0])
+}
+
+// 画像のオブジェクト（元画像とリサイズ版）のキー
+fn image_object_keys(image_id: Uuid, s3_key: String, original_format: &str) -> Vec<String> {
+    let format = rendition_format(original_format);
+    let mut keys = vec![s3_key];
+    keys.extend(RENDITIONS.iter().map(|rendition| rendition_key(image_id, *rendition, format)));
+    keys
+}
+
+// 保存済みのリサイズ版を返し、無ければ元画像から作って保存する
+async fn ensure_rendition(
+    state: &AppState,
+    image_id: Uuid,
+    s3_key: &str,
+    metadata: ImageMetadata,
+    rendition: ImageRendition,
+    format: ImageFormat,
+) -> Result<ObjectInfo, StatusCode> {
+    let key = rendition_key(image_id, rendition, format);
+    match state.store.head(&key).await {
+        Ok(object) => return Ok(object),
+        Err(StorageError::NotFound(_)) => {}
+        Err(e) => return Err(storage_error_status(&key, e)),
+    }
+
+    let original = state.store.get(s3_key).await.map_err(|e| storage_error_status(s3_key, e))?;
+
+    let max_side = rendition.max_side().unwrap_or(u32::MAX);
+    let data = tokio::task::spawn_blocking(move || render_rendition(&original, &metadata, max_side, format))
         .await
         .map_err(|e| {
-            eprintln!("Failed to get object from S3: {}", e);
+            eprintln!("Rendition task failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .map(Bytes::from)
+        .map_err(|e| {
+            eprintln!("Failed to render {}: {}", key, e);
             StatusCode::INTERNAL_SERVER_ERROR
         })?;
 
-    // 3. レスポンスを構築
-    let data = get_object_output
-        .body
-        .collect()
+    let size = data.len() as i64;
+    state.store
+        .put(&key, data, format.to_mime_type())
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .into_bytes();
+        .map_err(|e| storage_error_status(&key, e))?;
+    Ok(ObjectInfo { key, size })
+}
 
-    // axumのResponseBuilderを使用
-    let response = Response::builder()
-        .header(header::CONTENT_TYPE.as_str(), image.format)
-        .header(header::CACHE_CONTROL.as_str(), "public, max-age=31536000")
-        .body(Body::from(data))
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+// 長辺が max_side になるよう縮小する
+// 元画像のまま表示した場合と同じ向きになるよう、画素に反映していない EXIF の向きを反映する
+fn render_rendition(
+    data: &[u8],
+    metadata: &ImageMetadata,
+    max_side: u32,
+    format: ImageFormat,
+) -> Result<Vec<u8>, image::ImageError> {
+    let mut image = image::load_from_memory(data)?;
+    if let Some(orientation) = metadata.orientation.filter(|_| !metadata.orientation_applied) {
+        image = exif::apply_orientation(image, orientation);
+    }
+
+    let image = image.resize(max_side, max_side, FilterType::Triangle);
+    let (image, output) = match format {
+        // JPEG は透過・16bit を扱えないため 8bit RGB にする
+        ImageFormat::Jpeg => (
+            DynamicImage::ImageRgb8(image.to_rgb8()),
+            ImageOutputFormat::Jpeg(RENDITION_JPEG_QUALITY),
+        ),
+        format => (image, format.into()),
+    };
+
+    let mut encoded = Cursor::new(Vec::new());
+    image.write_to(&mut encoded, output)?;
+    Ok(encoded.into_inner())
+}
+
+
+// --- 画像の削除 ---
+
+// 一度に完全削除する画像の数
+const PURGE_BATCH: i64 = 100;
+const PURGE_INTERVAL: Duration = Duration::from_secs(3600);
+
+// 完全に削除した画像のオブジェクト（リサイズ版を含む）を削除する
+// 行は削除済みのため、削除できなかったオブジェクトはログに残す
+async fn delete_objects(store: &dyn ObjectStore, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = store.delete(key).await {
+            eprintln!("Failed to delete object {} of a deleted image: {}", key, e);
+        }
+    }
+}
+
+// 画像を論理削除（options.permanent の場合は完全に削除）する
+// locked・published のデータセットに含まれる画像が1枚でもあれば、force を指定しない限りどの画像も削除しない
+async fn delete_images(
+    state: &AppState,
+    image_ids: &[Uuid],
+    options: &DeleteImageOptions,
+) -> Result<DeleteImagesResponse, Response> {
+    let internal_error = |context: &str, e: sqlx::Error| {
+        eprintln!("{}: {}", context, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    };
+
+    let mut tx = state.db.begin().await.map_err(|e| internal_error("Failed to begin transaction", e))?;
+
+    // 削除・復元が同時に行われないようロックする
+    let known = sqlx::query_scalar!("SELECT id FROM images WHERE id = ANY($1) FOR UPDATE", image_ids)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| internal_error("Failed to lock images", e))?;
+
+    let unknown: Vec<Uuid> = image_ids.iter().filter(|id| !known.contains(id)).copied().collect();
+    if !unknown.is_empty() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": "unknown image ids", "image_ids": unknown })),
+        )
+            .into_response());
+    }
+
+    if !options.force {
+        let blocked = sqlx::query!(
+            r#"
+            SELECT di.image_id as "image_id!", d.id as dataset_id, d.name as dataset_name,
+                d.status as "status: DatasetStatus"
+            FROM dataset_images di JOIN datasets d ON d.id = di.dataset_id
+            WHERE di.image_id = ANY($1) AND d.status <> 'draft'
+            ORDER BY di.image_id, d.name
+            "#,
+            image_ids
+        )
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| internal_error("Failed to check dataset status", e))?;
+
+        if !blocked.is_empty() {
+            let images: Vec<Value> = blocked
+                .into_iter()
+                .map(|row| {
+                    json!({
+                        "image_id": row.image_id,
+                        "dataset_id": row.dataset_id,
+                        "dataset_name": row.dataset_name,
+                        "status": row.status,
+                    })
+                })
+                .collect();
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "images belong to locked or published datasets; set force to delete them anyway",
+                    "images": images,
+                })),
+            )
+                .into_response());
+        }
+    }
+
+    if options.permanent {
+        let deleted = sqlx::query!("DELETE FROM images WHERE id = ANY($1) RETURNING id, s3_key, format", image_ids)
+            .fetch_all(&mut *tx)
+            .await
+            .map_err(|e| internal_error("Failed to delete images", e))?;
+        tx.commit().await.map_err(|e| internal_error("Failed to commit image deletion", e))?;
+
+        let keys: Vec<String> = deleted
+            .into_iter()
+            .flat_map(|image| image_object_keys(image.id, image.s3_key, &image.format))
+            .collect();
+        delete_objects(state.store.as_ref(), &keys).await;
+        return Ok(DeleteImagesResponse {
+            image_ids: image_ids.to_vec(),
+            permanent: true,
+            restorable_until: None,
+        });
+    }
+
+    // 既に論理削除した画像の削除日時（復元期限）は変えない
+    sqlx::query!(
+        "UPDATE images SET deleted_at = NOW(), updated_at = NOW() WHERE id = ANY($1) AND deleted_at IS NULL",
+        image_ids
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| internal_error("Failed to delete images", e))?;
+
+    // 期限は保存した削除日時から求める（既に削除済みの画像を含む場合は最も早い期限）
+    let earliest_deleted_at = sqlx::query_scalar!("SELECT MIN(deleted_at) FROM images WHERE id = ANY($1)", image_ids)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| internal_error("Failed to fetch deletion time", e))?;
+    tx.commit().await.map_err(|e| internal_error("Failed to commit image deletion", e))?;
+
+    Ok(DeleteImagesResponse {
+        image_ids: image_ids.to_vec(),
+        permanent: false,
+        restorable_until: earliest_deleted_at
+            .map(|deleted_at| restore_deadline(deleted_at, state.config.image_restore_window_secs)),
+    })
+}
+
+// 論理削除した画像を復元できる期限（restore_image・purge_deleted_images と同じ判定）
+fn restore_deadline(deleted_at: DateTime<Utc>, window_secs: u64) -> DateTime<Utc> {
+    deleted_at + chrono::Duration::seconds(window_secs as i64)
+}
+
+// 画像の削除（?force=true・?permanent=true）
+pub async fn delete_image(
+    State(state): State<AppState>,
+    _: Authorized<DeleteImages>,
+    Path(image_id): Path<Uuid>,
+    Query(options): Query<DeleteImageOptions>,
+) -> Result<StatusCode, Response> {
+    delete_images(&state, &[image_id], &options).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 画像の一括削除
+pub async fn delete_images_bulk(
+    State(state): State<AppState>,
+    _: Authorized<DeleteImages>,
+    Json(payload): Json<DeleteImagesRequest>,
+) -> Result<Json<DeleteImagesResponse>, Response> {
+    let mut image_ids = payload.image_ids;
+    image_ids.sort();
+    image_ids.dedup();
+    delete_images(&state, &image_ids, &payload.options).await.map(Json)
+}
+
+// 論理削除した画像の復元（復元期間内のみ）
+pub async fn restore_image(
+    State(state): State<AppState>,
+    _: Authorized<DeleteImages>,
+    Path(image_id): Path<Uuid>,
+) -> Result<Json<ImageSummary>, Response> {
+    let restored = sqlx::query_as!(
+        ImageSummary,
+        r#"
+        UPDATE images SET deleted_at = NULL, updated_at = NOW()
+        WHERE id = $1 AND deleted_at >= NOW() - make_interval(secs => $2)
+        RETURNING
+            id, user_id, filename, original_filename, s3_bucket, s3_key, file_size,
+            width, height, format, classification_label, created_at as "created_at!", deleted_at, metadata
+        "#,
+        image_id,
+        state.config.image_restore_window_secs as f64
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| {
+        eprintln!("Failed to restore image: {}", e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
+    })?;
+
+    if let Some(image) = restored {
+        return Ok(Json(image));
+    }
+
+    // 復元できなかった理由
+    let deleted_at = sqlx::query_scalar!("SELECT deleted_at FROM images WHERE id = $1", image_id)
+        .fetch_optional(&state.db)
+        .await
+        .map_err(|e| {
+            eprintln!("Failed to fetch image: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        })?;
+
+    Err(match deleted_at {
+        None => StatusCode::NOT_FOUND.into_response(),
+        Some(None) => (StatusCode::CONFLICT, Json(json!({ "error": "image is not deleted" }))).into_response(),
+        Some(Some(_)) => (StatusCode::GONE, Json(json!({ "error": "the restore window has passed" }))).into_response(),
+    })
+}
+
+// 復元期間が過ぎた画像を、ストレージのオブジェクトとともに完全に削除する
+pub async fn purge_deleted_images(state: &AppState) -> Result<usize, sqlx::Error> {
+    let mut purged = 0;
+    loop {
+        let deleted = sqlx::query!(
+            r#"
+            DELETE FROM images WHERE id IN (
+                SELECT id FROM images
+                WHERE deleted_at < NOW() - make_interval(secs => $1)
+                ORDER BY deleted_at
+                LIMIT $2
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, s3_key, format
+            "#,
+            state.config.image_restore_window_secs as f64,
+            PURGE_BATCH
+        )
+        .fetch_all(&state.db)
+        .await?;
 
-    Ok(response)
+        let batch = deleted.len();
+        let keys: Vec<String> = deleted
+            .into_iter()
+            .flat_map(|image| image_object_keys(image.id, image.s3_key, &image.format))
+            .collect();
+        delete_objects(state.store.as_ref(), &keys).await;
+        purged += batch;
+        if (batch as i64) < PURGE_BATCH {
+            return Ok(purged);
+        }
+    }
 }
 
+// images に行が無いリサイズ版（完全削除と同時に作られたものなど）をストレージから削除する
+pub async fn purge_orphaned_renditions(state: &AppState) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+    let objects = state.store.list(RENDITION_PREFIX).await?;
+
+    // renditions/{画像ID}/{name}.{ext}
+    let mut keys_by_image: HashMap<Uuid, Vec<String>> = HashMap::new();
+    for object in objects {
+        let image_id = object
+            .key
+            .strip_prefix(RENDITION_PREFIX)
+            .and_then(|rest| rest.split('/').next())
+            .and_then(|id| Uuid::parse_str(id).ok());
+        if let Some(image_id) = image_id {
+            keys_by_image.entry(image_id).or_default().push(object.key);
+        }
+    }
+    if keys_by_image.is_empty() {
+        return Ok(0);
+    }
+
+    let image_ids: Vec<Uuid> = keys_by_image.keys().copied().collect();
+    let existing = sqlx::query_scalar!("SELECT id FROM images WHERE id = ANY($1)", &image_ids)
+        .fetch_all(&state.db)
+        .await?;
+    for image_id in existing {
+        keys_by_image.remove(&image_id);
+    }
+
+    let keys: Vec<String> = keys_by_image.into_values().flatten().collect();
+    delete_objects(state.store.as_ref(), &keys).await;
+    Ok(keys.len())
+}
+
+// 定期的に purge_deleted_images・purge_orphaned_renditions を実行する
+pub async fn run_image_purger(state: AppState) {
+    loop {
+        match purge_deleted_images(&state).await {
+            Ok(0) => {}
+            Ok(purged) => println!("Purged {} deleted images.", purged),
+            Err(e) => eprintln!("Failed to purge deleted images: {}", e),
+        }
+        match purge_orphaned_renditions(&state).await {
+            Ok(0) => {}
+            Ok(purged) => println!("Purged {} orphaned renditions.", purged),
+            Err(e) => eprintln!("Failed to purge orphaned renditions: {}", e),
+        }
+        tokio::time::sleep(PURGE_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(value: &'static str, size: u64) -> RangeRequest {
+        parse_range(&HeaderValue::from_static(value), size)
+    }
+
+    fn partial(start: u64, end: u64) -> RangeRequest {
+        RangeRequest::Partial(ByteRange { start, end })
+    }
+
+    #[test]
+    fn parse_range_accepts_single_ranges() {
+        assert_eq!(range("bytes=0-", 1000), partial(0, 999));
+        assert_eq!(range("bytes=0-99", 1000), partial(0, 99));
+        assert_eq!(range("bytes=900-5000", 1000), partial(900, 999));
+        assert_eq!(range("bytes=-500", 1000), partial(500, 999));
+        assert_eq!(range("bytes=-5000", 1000), partial(0, 999));
+    }
+
+    #[test]
+    fn parse_range_rejects_out_of_bounds_starts() {
+        assert_eq!(range("bytes=1000-", 1000), RangeRequest::Unsatisfiable);
+        assert_eq!(range("bytes=1500-2000", 1000), RangeRequest::Unsatisfiable);
+        assert_eq!(range("bytes=-0", 1000), RangeRequest::Unsatisfiable);
+        assert_eq!(range("bytes=0-", 0), RangeRequest::Unsatisfiable);
+    }
+
+    #[test]
+    fn parse_range_ignores_invalid_and_multiple_ranges() {
+        assert_eq!(range("bytes=5-3", 1000), RangeRequest::Full);
+        assert_eq!(range("bytes=0-1,5-9", 1000), RangeRequest::Full);
+        assert_eq!(range("items=0-1", 1000), RangeRequest::Full);
+        assert_eq!(range("bytes=a-b", 1000), RangeRequest::Full);
+    }
+
+    #[test]
+    fn etag_matches_weak_tags_lists_and_wildcard() {
+        let etag = "\"abc\"";
+        let matches = |value: &'static str| etag_matches(Some(&HeaderValue::from_static(value)), etag);
+
+        assert!(matches("\"abc\""));
+        assert!(matches("W/\"abc\""));
+        assert!(matches("\"other\", W/\"abc\""));
+        assert!(matches("*"));
+        assert!(!matches("\"other\""));
+        assert!(!etag_matches(None, etag));
+    }
+
+    #[test]
+    fn presigned_upload_keys_reject_paths_in_filenames() {
+        let key = |filename: &str| format!("images/{}_{}", Uuid::nil(), filename);
+
+        assert!(is_presigned_upload_key(&key("photo.jpg")));
+        assert!(is_presigned_upload_key(&key("IMG 0001.jpg")));
+        assert!(!is_presigned_upload_key(&key("")));
+        assert!(!is_presigned_upload_key(&key("../photo.jpg")));
+        assert!(!is_presigned_upload_key(&key("dir/photo.jpg")));
+        assert!(!is_presigned_upload_key(&key("dir\\photo.jpg")));
+        assert!(!is_presigned_upload_key("images/not-a-uuid_photo.jpg"));
+    }
+
+    #[test]
+    fn deleting_twice_keeps_the_first_restore_deadline() {
+        let window_secs = 7 * 24 * 3600;
+        let first_deleted_at = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let second_request_at = first_deleted_at + chrono::Duration::hours(1);
+
+        // 2回目の削除でも deleted_at は最初の削除日時のまま
+        let deadline = restore_deadline(first_deleted_at, window_secs);
+        assert_eq!(deadline, first_deleted_at + chrono::Duration::days(7));
+        assert!(deadline < restore_deadline(second_request_at, window_secs));
+    }
+
+    #[test]
+    fn stale_if_range_returns_full_content() {
+        let etag = "\"abc\"";
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_static("bytes=0-99"));
+        assert_eq!(requested_range(&headers, etag, 1000), partial(0, 99));
+
+        headers.insert(header::IF_RANGE, HeaderValue::from_static("\"abc\""));
+        assert_eq!(requested_range(&headers, etag, 1000), partial(0, 99));
+
+        headers.insert(header::IF_RANGE, HeaderValue::from_static("\"stale\""));
+        assert_eq!(requested_range(&headers, etag, 1000), RangeRequest::Full);
+    }
+}
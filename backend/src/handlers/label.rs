@@ -0,0 +1,585 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde_json::json;
+use sqlx::{PgConnection, PgPool, Postgres, Transaction};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::{
+    auth::policy::{Authorized, ManageLabels, Read},
+    models::{
+        CreateLabelRequest, Label, LabelAuditAction, LabelAuditLog, LabelChangeResponse, ListLabelsQuery,
+        MergeLabelsRequest, RenameLabelRequest, UpdateLabelRequest,
+    },
+    utils::json::JsonExtractor,
+    AppState,
+};
+
+fn db_error(context: &str, e: sqlx::Error) -> Response {
+    eprintln!("{}: {}", context, e);
+    match e.as_database_error() {
+        Some(db) if db.is_unique_violation() => {
+            (StatusCode::CONFLICT, Json(json!({ "error": "label name already exists" }))).into_response()
+        }
+        // 存在しない親ラベル
+        Some(db) if db.is_foreign_key_violation() => {
+            (StatusCode::BAD_REQUEST, Json(json!({ "error": "unknown parent label" }))).into_response()
+        }
+        // 色の形式（#rrggbb）
+        Some(db) if db.is_check_violation() => {
+            (StatusCode::BAD_REQUEST, Json(json!({ "error": "color must be in #rrggbb format" }))).into_response()
+        }
+        _ => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    }
+}
+
+// アノテーションに付けられるラベルか確認する
+// アーカイブしたラベルは、既にそのラベルが付いているアノテーション（current）の更新にのみ使える
+// アノテーションはプロジェクトを持たないため、同じ名前のラベルがどれか1つでもアーカイブされていなければ使える
+pub async fn check_annotation_label(pool: &PgPool, label: &str, current: Option<&str>) -> Result<(), Response> {
+    let is_archived = sqlx::query_scalar!("SELECT bool_and(is_archived) FROM labels WHERE name = $1", label)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| db_error("Failed to look up label", e))?;
+
+    let error = match is_archived {
+        None => "unknown label",
+        Some(true) if current != Some(label) => "label is archived",
+        Some(_) => return Ok(()),
+    };
+    Err((StatusCode::BAD_REQUEST, Json(json!({ "error": error, "label": label }))).into_response())
+}
+
+// parent_id を親にすると親子関係が循環するか（自分自身や子孫を親にする場合）
+async fn creates_cycle(pool: &PgPool, id: i32, parent_id: i32) -> Result<bool, Response> {
+    sqlx::query_scalar!(
+        r#"
+        WITH RECURSIVE ancestors AS (
+            SELECT id, parent_id FROM labels WHERE id = $1
+            UNION
+            SELECT l.id, l.parent_id FROM labels l JOIN ancestors a ON l.id = a.parent_id
+        )
+        SELECT EXISTS(SELECT 1 FROM ancestors WHERE id = $2) as "exists!"
+        "#,
+        parent_id,
+        id
+    )
+    .fetch_one(pool)
+    .await
+    .map_err(|e| db_error("Failed to check label hierarchy", e))
+}
+
+// ラベル一覧（クラスIDの順）
+pub async fn list_labels(
+    State(state): State<AppState>,
+    _: Authorized<Read>,
+    Query(query): Query<ListLabelsQuery>,
+) -> Result<Json<Vec<Label>>, Response> {
+    sqlx::query_as!(
+        Label,
+        r#"
+        SELECT id, project_id, name, color, description, parent_id, is_archived, created_at, updated_at
+        FROM labels
+        WHERE ($1 OR NOT is_archived)
+          AND ($2::UUID IS NULL OR project_id IS NULL OR project_id = $2)
+        ORDER BY id
+        "#,
+        query.include_archived,
+        query.project_id
+    )
+    .fetch_all(&state.db)
+    .await
+    .map(Json)
+    .map_err(|e| db_error("Failed to list labels", e))
+}
+
+pub async fn get_label(
+    State(state): State<AppState>,
+    _: Authorized<Read>,
+    Path(id): Path<i32>,
+) -> Result<Json<Label>, Response> {
+    sqlx::query_as!(
+        Label,
+        r#"
+        SELECT id, project_id, name, color, description, parent_id, is_archived, created_at, updated_at
+        FROM labels WHERE id = $1
+        "#,
+        id
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| db_error("Failed to fetch label", e))?
+    .map(Json)
+    .ok_or_else(|| StatusCode::NOT_FOUND.into_response())
+}
+
+// ラベルの作成（クラスIDは作成順に振られる）
+pub async fn create_label(
+    State(state): State<AppState>,
+    _: Authorized<ManageLabels>,
+    JsonExtractor(payload): JsonExtractor<CreateLabelRequest>,
+) -> Result<(StatusCode, Json<Label>), Response> {
+    // 失敗した INSERT でもクラスIDが消費され欠番になるため、名前の重複と親の有無は先に確認する
+    let existing = sqlx::query!(
+        r#"
+        SELECT
+            EXISTS(SELECT 1 FROM labels WHERE name = $1 AND project_id IS NOT DISTINCT FROM $3) as "name_taken!",
+            ($2::INTEGER IS NULL OR EXISTS(
+                SELECT 1 FROM labels WHERE id = $2 AND (project_id IS NULL OR project_id IS NOT DISTINCT FROM $3)
+            )) as "parent_exists!"
+        "#,
+        payload.name,
+        payload.parent_id,
+        payload.project_id
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| db_error("Failed to check label", e))?;
+
+    if existing.name_taken {
+        return Err((StatusCode::CONFLICT, Json(json!({ "error": "label name already exists" }))).into_response());
+    }
+    if !existing.parent_exists {
+        return Err((StatusCode::BAD_REQUEST, Json(json!({ "error": "unknown parent label" }))).into_response());
+    }
+
+    let label = sqlx::query_as!(
+        Label,
+        r#"
+        INSERT INTO labels (name, color, description, parent_id, project_id)
+        VALUES ($1, COALESCE($2, '#ff0000'), $3, $4, $5)
+        RETURNING id, project_id, name, color, description, parent_id, is_archived, created_at, updated_at
+        "#,
+        payload.name,
+        payload.color,
+        payload.description,
+        payload.parent_id,
+        payload.project_id
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| db_error("Failed to create label", e))?;
+
+    Ok((StatusCode::CREATED, Json(label)))
+}
+
+// 色・説明・親・アーカイブの変更
+pub async fn update_label(
+    State(state): State<AppState>,
+    _: Authorized<ManageLabels>,
+    Path(id): Path<i32>,
+    JsonExtractor(payload): JsonExtractor<UpdateLabelRequest>,
+) -> Result<Json<Label>, Response> {
+    if let Some(Some(parent_id)) = payload.parent_id {
+        // 親は共有ラベルか同じプロジェクトのラベルに限る
+        let parent_in_scope = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1 FROM labels parent, labels child
+                WHERE parent.id = $1 AND child.id = $2
+                  AND (parent.project_id IS NULL OR parent.project_id IS NOT DISTINCT FROM child.project_id)
+            ) as "exists!"
+            "#,
+            parent_id,
+            id
+        )
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| db_error("Failed to check parent label", e))?;
+        if !parent_in_scope {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "parent label must be shared or in the same project" })),
+            )
+                .into_response());
+        }
+        if creates_cycle(&state.db, id, parent_id).await? {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                Json(json!({ "error": "a label cannot be its own ancestor" })),
+            )
+                .into_response());
+        }
+    }
+
+    sqlx::query_as!(
+        Label,
+        r#"
+        UPDATE labels
+        SET
+            color = COALESCE($1, color),
+            description = COALESCE($2, description),
+            parent_id = CASE WHEN $3 THEN $4 ELSE parent_id END,
+            is_archived = COALESCE($5, is_archived),
+            updated_at = NOW()
+        WHERE id = $6
+        RETURNING id, project_id, name, color, description, parent_id, is_archived, created_at, updated_at
+        "#,
+        payload.color,
+        payload.description,
+        payload.parent_id.is_some(),
+        payload.parent_id.flatten(),
+        payload.is_archived,
+        id
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| db_error("Failed to update label", e))?
+    .map(Json)
+    .ok_or_else(|| StatusCode::NOT_FOUND.into_response())
+}
+
+// アノテーションで使われていないラベルのみ削除できる（使われている場合はアーカイブする）
+// 削除したラベルのクラスIDは再利用しない
+pub async fn delete_label(
+    State(state): State<AppState>,
+    _: Authorized<ManageLabels>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, Response> {
+    let mut tx = state.db.begin().await.map_err(|e| db_error("Failed to begin transaction", e))?;
+
+    let name = sqlx::query_scalar!("SELECT name FROM labels WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| db_error("Failed to fetch label", e))?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    let annotations = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM annotations WHERE label = $1"#,
+        name
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to count annotations", e))?;
+
+    if annotations > 0 {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "label is in use; archive it instead", "annotations": annotations })),
+        )
+            .into_response());
+    }
+
+    sqlx::query!("DELETE FROM labels WHERE id = $1", id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| db_error("Failed to delete label", e))?;
+
+    tx.commit().await.map_err(|e| db_error("Failed to commit label deletion", e))?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// アノテーションはプロジェクトを持たず名前で対応付けるため、
+// 他のプロジェクトと共有している名前のアノテーションは名前変更・統合で書き換えない
+fn shared_name_conflict(names: &[String]) -> Response {
+    (
+        StatusCode::CONFLICT,
+        Json(json!({ "error": "label name is also used by another project", "labels": names })),
+    )
+        .into_response()
+}
+
+// ラベルごとのアノテーション数
+async fn count_annotations(conn: &mut PgConnection, labels: &[String]) -> Result<HashMap<String, i64>, Response> {
+    let rows = sqlx::query!(
+        r#"SELECT label, COUNT(*) as "count!" FROM annotations WHERE label = ANY($1) GROUP BY label"#,
+        labels
+    )
+    .fetch_all(conn)
+    .await
+    .map_err(|e| db_error("Failed to count annotations", e))?;
+
+    let mut counts: HashMap<String, i64> = labels.iter().map(|label| (label.clone(), 0)).collect();
+    counts.extend(rows.into_iter().map(|row| (row.label, row.count)));
+    Ok(counts)
+}
+
+// 名前変更・統合を確定して監査ログを記録する
+// dry_run の場合は同じ変更を行った上でロールバックするため、実行した場合と同じ結果を返す
+async fn finish_label_change(
+    mut tx: Transaction<'_, Postgres>,
+    user_id: Uuid,
+    action: LabelAuditAction,
+    source_labels: Vec<String>,
+    mut change: LabelChangeResponse,
+) -> Result<Json<LabelChangeResponse>, Response> {
+    if change.dry_run {
+        tx.rollback().await.map_err(|e| db_error("Failed to roll back label change", e))?;
+    } else {
+        let audit_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO label_audit_logs (user_id, action, source_labels, target_label, target_label_id, annotations_changed)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id
+            "#,
+            user_id,
+            action as LabelAuditAction,
+            &source_labels,
+            change.label.name,
+            change.label.id,
+            change.annotations_changed as i32
+        )
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| db_error("Failed to write label audit log", e))?;
+
+        tx.commit().await.map_err(|e| db_error("Failed to commit label change", e))?;
+        eprintln!(
+            "User {} {:?} labels {:?} -> '{}' ({} annotations)",
+            user_id, action, source_labels, change.label.name, change.annotations_changed
+        );
+        change.audit_id = Some(audit_id);
+    }
+    Ok(Json(change))
+}
+
+// ラベル名の変更（クラスIDは変わらない）
+// 既存のラベルと同じ名前にする場合は統合を使う
+pub async fn rename_label(
+    State(state): State<AppState>,
+    Authorized(user, _): Authorized<ManageLabels>,
+    Path(id): Path<i32>,
+    JsonExtractor(payload): JsonExtractor<RenameLabelRequest>,
+) -> Result<Json<LabelChangeResponse>, Response> {
+    let mut tx = state.db.begin().await.map_err(|e| db_error("Failed to begin transaction", e))?;
+
+    let old_name = sqlx::query_scalar!("SELECT name FROM labels WHERE id = $1 FOR UPDATE", id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| db_error("Failed to fetch label", e))?
+        .ok_or_else(|| StatusCode::NOT_FOUND.into_response())?;
+
+    if old_name == payload.name {
+        return Err((StatusCode::BAD_REQUEST, Json(json!({ "error": "the label already has this name" }))).into_response());
+    }
+
+    let names = sqlx::query!(
+        r#"
+        SELECT
+            EXISTS(
+                SELECT 1 FROM labels
+                WHERE name = $1 AND project_id IS NOT DISTINCT FROM (SELECT project_id FROM labels WHERE id = $3)
+            ) as "name_taken!",
+            EXISTS(SELECT 1 FROM labels WHERE name = $1) as "name_used!",
+            EXISTS(SELECT 1 FROM labels WHERE name = $2 AND id <> $3) as "old_name_shared!"
+        "#,
+        payload.name,
+        old_name,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to check label name", e))?;
+
+    if names.name_taken {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({ "error": "label name already exists; merge the labels instead" })),
+        )
+            .into_response());
+    }
+    if names.name_used {
+        return Err(shared_name_conflict(&[payload.name]));
+    }
+    if names.old_name_shared {
+        return Err(shared_name_conflict(&[old_name]));
+    }
+
+    let annotations_by_label = count_annotations(&mut tx, std::slice::from_ref(&old_name)).await?;
+
+    let label = sqlx::query_as!(
+        Label,
+        r#"
+        UPDATE labels SET name = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING id, project_id, name, color, description, parent_id, is_archived, created_at, updated_at
+        "#,
+        payload.name,
+        id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to rename label", e))?;
+
+    let annotations_changed = sqlx::query!(
+        "UPDATE annotations SET label = $1, updated_at = NOW() WHERE label = $2",
+        payload.name,
+        old_name
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to rename annotation labels", e))?
+    .rows_affected() as i64;
+
+    let change = LabelChangeResponse {
+        dry_run: payload.dry_run,
+        label,
+        annotations_changed,
+        annotations_by_label,
+        audit_id: None,
+    };
+    finish_label_change(tx, user.id, LabelAuditAction::Rename, vec![old_name], change).await
+}
+
+// 複数のラベルを1つに統合する
+// 統合元のアノテーションは統合先のラベルに変更し、統合元の子ラベルは統合先の子にする
+pub async fn merge_labels(
+    State(state): State<AppState>,
+    Authorized(user, _): Authorized<ManageLabels>,
+    JsonExtractor(payload): JsonExtractor<MergeLabelsRequest>,
+) -> Result<Json<LabelChangeResponse>, Response> {
+    let mut source_ids = payload.source_ids.clone();
+    source_ids.sort();
+    source_ids.dedup();
+
+    if source_ids.contains(&payload.target_id) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "target_id must not be in source_ids" })),
+        )
+            .into_response());
+    }
+
+    let mut tx = state.db.begin().await.map_err(|e| db_error("Failed to begin transaction", e))?;
+
+    // デッドロックを避けるため、常に id の順にロックする
+    let rows = sqlx::query!(
+        "SELECT id, name, project_id FROM labels WHERE id = ANY($1) OR id = $2 ORDER BY id FOR UPDATE",
+        &source_ids,
+        payload.target_id
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to fetch labels", e))?;
+
+    let unknown: Vec<i32> = source_ids
+        .iter()
+        .chain(std::iter::once(&payload.target_id))
+        .filter(|id| !rows.iter().any(|row| row.id == **id))
+        .copied()
+        .collect();
+    if !unknown.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "unknown label ids", "label_ids": unknown })),
+        )
+            .into_response());
+    }
+
+    if rows.iter().any(|row| row.project_id != rows[0].project_id) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "labels of different projects cannot be merged" })),
+        )
+            .into_response());
+    }
+
+    let target_name = rows
+        .iter()
+        .find(|row| row.id == payload.target_id)
+        .map(|row| row.name.clone())
+        .unwrap_or_default();
+    let source_names: Vec<String> = rows
+        .into_iter()
+        .filter(|row| row.id != payload.target_id)
+        .map(|row| row.name)
+        .collect();
+
+    // 統合元と同じ名前のラベルが他のプロジェクトにあると、そのアノテーションまで変更してしまう
+    let shared_names = sqlx::query_scalar!(
+        "SELECT DISTINCT name FROM labels WHERE name = ANY($1) AND NOT (id = ANY($2)) ORDER BY name",
+        &source_names,
+        &source_ids
+    )
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to check label names", e))?;
+    if !shared_names.is_empty() {
+        return Err(shared_name_conflict(&shared_names));
+    }
+
+    let annotations_by_label = count_annotations(&mut tx, &source_names).await?;
+
+    let annotations_changed = sqlx::query!(
+        "UPDATE annotations SET label = $1, updated_at = NOW() WHERE label = ANY($2)",
+        target_name,
+        &source_names
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to merge annotation labels", e))?
+    .rows_affected() as i64;
+
+    // 統合先の祖先にあたる子ラベルは、循環しないよう付け替えずに親を外す（削除時に NULL になる）
+    sqlx::query!(
+        r#"
+        WITH RECURSIVE ancestors AS (
+            SELECT parent_id AS id FROM labels WHERE id = $1
+            UNION
+            SELECT l.parent_id FROM labels l JOIN ancestors a ON l.id = a.id
+        )
+        UPDATE labels SET parent_id = $1, updated_at = NOW()
+        WHERE parent_id = ANY($2)
+          AND id <> $1
+          AND id NOT IN (SELECT id FROM ancestors WHERE id IS NOT NULL)
+        "#,
+        payload.target_id,
+        &source_ids
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to move child labels", e))?;
+
+    sqlx::query!("DELETE FROM labels WHERE id = ANY($1)", &source_ids)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| db_error("Failed to delete merged labels", e))?;
+
+    let label = sqlx::query_as!(
+        Label,
+        r#"
+        UPDATE labels SET updated_at = NOW()
+        WHERE id = $1
+        RETURNING id, project_id, name, color, description, parent_id, is_archived, created_at, updated_at
+        "#,
+        payload.target_id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| db_error("Failed to fetch merged label", e))?;
+
+    let change = LabelChangeResponse {
+        dry_run: payload.dry_run,
+        label,
+        annotations_changed,
+        annotations_by_label,
+        audit_id: None,
+    };
+    finish_label_change(tx, user.id, LabelAuditAction::Merge, source_names, change).await
+}
+
+// ラベルの名前変更・統合の履歴（新しい順に100件）
+pub async fn list_label_audit_logs(
+    State(state): State<AppState>,
+    _: Authorized<ManageLabels>,
+) -> Result<Json<Vec<LabelAuditLog>>, Response> {
+    sqlx::query_as!(
+        LabelAuditLog,
+        r#"
+        SELECT
+            id, user_id,
+            action as "action: LabelAuditAction",
+            source_labels, target_label, target_label_id, annotations_changed, created_at
+        FROM label_audit_logs
+        ORDER BY created_at DESC
+        LIMIT 100
+        "#
+    )
+    .fetch_all(&state.db)
+    .await
+    .map(Json)
+    .map_err(|e| db_error("Failed to list label audit logs", e))
+}
@@ -2,5 +2,8 @@ pub mod annotation;
 pub mod dataset; // この行を追加
 pub mod export;
 pub mod image; // この行を追加
+pub mod label;
+pub mod storage;
+pub mod user;
 
 
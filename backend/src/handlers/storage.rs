@@ -0,0 +1,92 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::Response,
+};
+use serde::Deserialize;
+
+use crate::{
+    storage::{SignedMethod, StorageError},
+    AppState,
+};
+
+// 署名付きURLで受け付けるアップロードの上限サイズ
+pub const MAX_SIGNED_UPLOAD_BYTES: usize = 100 * 1024 * 1024;
+
+// ローカルストレージが発行した署名付きURLのクエリ
+#[derive(Deserialize)]
+pub struct SignedUrlQuery {
+    expires: i64,
+    signature: String,
+}
+
+fn verify(
+    state: &AppState,
+    method: SignedMethod,
+    key: &str,
+    query: &SignedUrlQuery,
+) -> Result<(), StatusCode> {
+    if state
+        .store
+        .verify_signed_url(method, key, query.expires, &query.signature)
+    {
+        Ok(())
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+fn storage_status(e: &StorageError) -> StatusCode {
+    match e {
+        StorageError::NotFound(_) => StatusCode::NOT_FOUND,
+        StorageError::InvalidKey(_) => StatusCode::BAD_REQUEST,
+        StorageError::Backend(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+// 署名付きURLによるダウンロード
+pub async fn download_signed_object(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<SignedUrlQuery>,
+) -> Result<Response<Body>, StatusCode> {
+    verify(&state, SignedMethod::Get, &key, &query)?;
+
+    let stream = state.store.get_stream(&key, None).await.map_err(|e| {
+        eprintln!("Failed to read object {}: {}", key, e);
+        storage_status(&e)
+    })?;
+
+    let content_type = image::ImageFormat::from_path(&key)
+        .map(|format| format.to_mime_type())
+        .unwrap_or("application/octet-stream");
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::from_stream(stream))
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+// 署名付きURLによるアップロード（S3の presigned PUT と同じく生のボディを受け取る）
+pub async fn upload_signed_object(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(query): Query<SignedUrlQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<StatusCode, StatusCode> {
+    verify(&state, SignedMethod::Put, &key, &query)?;
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("application/octet-stream");
+
+    state.store.put(&key, body, content_type).await.map_err(|e| {
+        eprintln!("Failed to write object {}: {}", key, e);
+        storage_status(&e)
+    })?;
+
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,186 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    auth::policy::{Authorized, ManageUsers},
+    models::{CreateUserRequest, ListUsersQuery, UpdateUserRoleRequest, User, UserInfo, UserListResponse, UserRole},
+    utils::json::JsonExtractor,
+    AppState,
+};
+
+const DEFAULT_PER_PAGE: i64 = 50;
+const MAX_PER_PAGE: i64 = 200;
+
+fn db_error(context: &str, e: sqlx::Error) -> StatusCode {
+    eprintln!("{}: {}", context, e);
+    match e.as_database_error() {
+        // cognito_sub・username・email の重複
+        Some(db) if db.is_unique_violation() => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+// ログイン中のユーザー自身の情報
+pub async fn get_me(user: UserInfo) -> Json<UserInfo> {
+    Json(user)
+}
+
+// ユーザー一覧（管理者のみ）
+pub async fn list_users(
+    State(state): State<AppState>,
+    _: Authorized<ManageUsers>,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<Json<UserListResponse>, StatusCode> {
+    let page = query.page.unwrap_or(1);
+    if page < 1 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let per_page = query.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE);
+    // 極端に大きい page でも桁あふれしないようにする（範囲外のページは空になる）
+    let offset = (page - 1).saturating_mul(per_page);
+
+    let users = sqlx::query_as!(
+        User,
+        r#"
+        SELECT
+            id, cognito_sub, username, email,
+            role as "role: UserRole",
+            is_active,
+            created_at as "created_at!",
+            updated_at as "updated_at!"
+        FROM users
+        ORDER BY created_at, id
+        LIMIT $1 OFFSET $2
+        "#,
+        per_page,
+        offset
+    )
+    .fetch_all(&state.db)
+    .await
+    .map_err(|e| db_error("Failed to list users", e))?;
+
+    let total = sqlx::query_scalar!(r#"SELECT COUNT(*) as "count!" FROM users"#)
+        .fetch_one(&state.db)
+        .await
+        .map_err(|e| db_error("Failed to count users", e))?;
+
+    Ok(Json(UserListResponse {
+        users,
+        total,
+        page,
+        per_page,
+    }))
+}
+
+// ユーザーの事前登録（管理者のみ）
+pub async fn create_user(
+    State(state): State<AppState>,
+    _: Authorized<ManageUsers>,
+    JsonExtractor(payload): JsonExtractor<CreateUserRequest>,
+) -> Result<(StatusCode, Json<User>), StatusCode> {
+    let user = sqlx::query_as!(
+        User,
+        r#"
+        INSERT INTO users (cognito_sub, username, email, role)
+        VALUES ($1, $2, $3, $4)
+        RETURNING
+            id, cognito_sub, username, email,
+            role as "role: UserRole",
+            is_active,
+            created_at as "created_at!",
+            updated_at as "updated_at!"
+        "#,
+        payload.cognito_sub,
+        payload.username,
+        payload.email,
+        payload.role as UserRole
+    )
+    .fetch_one(&state.db)
+    .await
+    .map_err(|e| db_error("Failed to create user", e))?;
+
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+// ロールの変更（管理者のみ）
+pub async fn update_user_role(
+    State(state): State<AppState>,
+    Authorized(admin, _): Authorized<ManageUsers>,
+    Path(id): Path<Uuid>,
+    JsonExtractor(payload): JsonExtractor<UpdateUserRoleRequest>,
+) -> Result<Json<User>, StatusCode> {
+    // 管理者が自分自身の権限を外して、管理者不在になるのを防ぐ
+    if id == admin.id && payload.role != UserRole::Admin {
+        eprintln!("Admin {} attempted to change their own role", admin.id);
+        return Err(StatusCode::CONFLICT);
+    }
+
+    sqlx::query_as!(
+        User,
+        r#"
+        UPDATE users SET role = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING
+            id, cognito_sub, username, email,
+            role as "role: UserRole",
+            is_active,
+            created_at as "created_at!",
+            updated_at as "updated_at!"
+        "#,
+        payload.role as UserRole,
+        id
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| db_error("Failed to update user role", e))?
+    .map(Json)
+    .ok_or(StatusCode::NOT_FOUND)
+}
+
+// ユーザーの無効化・再有効化（管理者のみ）
+pub async fn deactivate_user(
+    State(state): State<AppState>,
+    Authorized(admin, _): Authorized<ManageUsers>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<User>, StatusCode> {
+    if id == admin.id {
+        eprintln!("Admin {} attempted to deactivate themselves", admin.id);
+        return Err(StatusCode::CONFLICT);
+    }
+    set_active(&state, id, false).await
+}
+
+pub async fn reactivate_user(
+    State(state): State<AppState>,
+    _: Authorized<ManageUsers>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<User>, StatusCode> {
+    set_active(&state, id, true).await
+}
+
+async fn set_active(state: &AppState, id: Uuid, is_active: bool) -> Result<Json<User>, StatusCode> {
+    sqlx::query_as!(
+        User,
+        r#"
+        UPDATE users SET is_active = $1, updated_at = NOW()
+        WHERE id = $2
+        RETURNING
+            id, cognito_sub, username, email,
+            role as "role: UserRole",
+            is_active,
+            created_at as "created_at!",
+            updated_at as "updated_at!"
+        "#,
+        is_active,
+        id
+    )
+    .fetch_optional(&state.db)
+    .await
+    .map_err(|e| db_error("Failed to update user status", e))?
+    .map(Json)
+    .ok_or(StatusCode::NOT_FOUND)
+}
@@ -1,10 +1,11 @@
 use axum::{
-    http::{header::CONTENT_TYPE, Method},
-    routing::{get, post},
+    extract::DefaultBodyLimit,
+    http::{header::{HeaderName, AUTHORIZATION, CONTENT_TYPE}, Method},
+    routing::{get, post, put},
     Router,
 };
-use sqlx::postgres::PgPoolOptions;
-use std::env;
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::{env, sync::Arc};
 use tower::ServiceExt;
 use tower_http::cors::{Any, CorsLayer};
 
@@ -14,8 +15,12 @@ use lambda_http::http::Uri;
 use lambda_http::request::RequestContext;
 
 
+mod auth;
+mod config;
 mod models;
 mod handlers;
+mod migrate;
+mod storage;
 mod utils;
 
 use crate::handlers::{
@@ -23,19 +28,37 @@ use crate::handlers::{
         create_annotation, delete_annotation, get_annotation, get_annotations_for_image,
         update_annotation, get_available_labels,
     },
-    dataset::create_dataset,
-    export::export_dataset,
+    dataset::{
+        add_dataset_images, create_dataset, delete_dataset, get_dataset, list_datasets,
+        remove_dataset_images, update_dataset,
+    },
+    export::{
+        create_export_job, download_export, export_dataset, export_persisted_dataset, get_export_job,
+        list_export_jobs, run_worker,
+    },
     image::{
-        search_images, get_image, generate_presigned_url, register_uploaded_image,
+        delete_image, delete_images_bulk, generate_presigned_url, get_image, list_images, purge_deleted_images, purge_orphaned_renditions,
+        register_uploaded_image, restore_image, run_image_purger, search_images, upload_image, IMAGE_HEIGHT_HEADER,
+        IMAGE_WIDTH_HEADER,
+    },
+    label::{
+        create_label, delete_label, get_label, list_label_audit_logs, list_labels, merge_labels, rename_label,
+        update_label,
     },
+    storage::{download_signed_object, upload_signed_object, MAX_SIGNED_UPLOAD_BYTES},
+    user::{create_user, deactivate_user, get_me, list_users, reactivate_user, update_user_role},
 };
+use crate::auth::Authenticator;
+use crate::config::{Config, DatabaseConfig, StorageBackend};
+use crate::storage::{LocalStore, ObjectStore, S3Store};
 use aws_sdk_s3::Client as S3Client;
 
 #[derive(Clone)]
 pub struct AppState {
     db: sqlx::PgPool,
-    s3_client: S3Client,
-    s3_bucket: String,
+    store: Arc<dyn ObjectStore>,
+    auth: Arc<Authenticator>,
+    config: Arc<Config>,
 }
 
 #[tokio::main]
@@ -48,50 +71,168 @@ async fn main() -> Result<(), Error> {
         .without_time()
         .init();
 
-    let db_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-    let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&db_url)
-        .await
-        .expect("Failed to create pool.");
+    // `kg-annotation-backend migrate [up|status|dry-run|seed]`
+    // データベースの設定だけで動かせるよう、全体の設定を読み込む前に処理する
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        let database = match DatabaseConfig::load() {
+            Ok(database) => database,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        };
+        let pool = connect_database(&database).await;
+        return migrate::run_cli(&args[1..], &pool).await;
+    }
+
+    let config = match Config::load() {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let pool = connect_database(&DatabaseConfig {
+        database_url: config.database_url.clone(),
+        database_max_connections: config.database_max_connections,
+    })
+    .await;
 
-    let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
-    let s3_client = S3Client::new(&aws_config);
-    let s3_bucket = env::var("S3_BUCKET_NAME").expect("S3_BUCKET_NAME must be set");
+    if config.auto_migrate {
+        migrate::run(&pool).await?;
+        println!("Database migrations are up to date.");
+    }
+
+    let store: Arc<dyn ObjectStore> = match config.storage_backend {
+        StorageBackend::S3 => {
+            let aws_config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+                .region(aws_config::Region::new(config.aws_region.clone()))
+                .load()
+                .await;
+            Arc::new(S3Store::new(S3Client::new(&aws_config), config.s3_bucket.clone()))
+        }
+        StorageBackend::Local => Arc::new(LocalStore::new(
+            config.local_storage_path.clone(),
+            config.public_base_url.clone(),
+            config.storage_signing_secret.clone(),
+        )),
+    };
 
     let state = AppState {
         db: pool.clone(),
-        s3_client,
-        s3_bucket,
+        store,
+        auth: Arc::new(Authenticator::from_config(&config)),
+        config: config.clone(),
     };
 
+    // `kg-annotation-backend export-worker`: エクスポートジョブの処理だけを行う（Lambda 構成では別プロセスとして動かす）
+    if args.first().map(String::as_str) == Some("export-worker") {
+        println!("Export worker started.");
+        tokio::select! {
+            _ = run_worker(state) => {},
+            _ = shutdown_signal() => {},
+        }
+        return Ok(());
+    }
+
+    // `kg-annotation-backend purge-images`: 復元期間が過ぎた画像と、画像の無いリサイズ版を削除する（Lambda 構成では定期実行する）
+    if args.first().map(String::as_str) == Some("purge-images") {
+        let purged = purge_deleted_images(&state).await?;
+        println!("Purged {} deleted images.", purged);
+        let purged = purge_orphaned_renditions(&state).await?;
+        println!("Purged {} orphaned renditions.", purged);
+        return Ok(());
+    }
+
+    if !is_running_on_lambda() {
+        tokio::spawn(run_image_purger(state.clone()));
+    }
+
+    if config.export_worker && !is_running_on_lambda() {
+        tokio::spawn(run_worker(state.clone()));
+    }
+
     let app = Router::new()
         .route("/api/annotations", post(create_annotation).get(get_annotations_for_image))
         .route("/api/annotations/labels", get(get_available_labels))
         .route("/api/annotations/image/:image_id", get(get_annotations_for_image))
         .route("/api/annotations/:id", get(get_annotation).put(update_annotation).delete(delete_annotation))
-        .route("/api/datasets", post(create_dataset))
+        .route("/api/labels", get(list_labels).post(create_label))
+        .route("/api/labels/merge", post(merge_labels))
+        .route("/api/labels/audit", get(list_label_audit_logs))
+        .route("/api/labels/:id", get(get_label).put(update_label).delete(delete_label))
+        .route("/api/labels/:id/rename", post(rename_label))
+        .route("/api/datasets", post(create_dataset).get(list_datasets))
+        .route("/api/datasets/:id", get(get_dataset).put(update_dataset).delete(delete_dataset))
+        .route("/api/datasets/:id/images", post(add_dataset_images).delete(remove_dataset_images))
+        .route("/api/datasets/:id/export", post(export_persisted_dataset))
+        .route(
+            "/api/images",
+            get(list_images)
+                .post(upload_image)
+                .layer(DefaultBodyLimit::max(config.max_upload_bytes))
+                .delete(delete_images_bulk),
+        )
         .route("/api/images/register", post(register_uploaded_image))
         .route("/api/images/presigned-url", post(generate_presigned_url))
-        .route("/api/images/:id", get(get_image))
+        .route("/api/images/:id", get(get_image).delete(delete_image))
+        .route("/api/images/:id/restore", post(restore_image))
         .route("/api/images/search", post(search_images))
         .route("/api/export", post(export_dataset))
+        .route("/api/exports", post(create_export_job).get(list_export_jobs))
+        .route("/api/exports/:id", get(get_export_job))
+        .route("/api/exports/:id/download", get(download_export))
+        .route("/api/me", get(get_me))
+        .route("/api/users", get(list_users).post(create_user))
+        .route("/api/users/:id/role", put(update_user_role))
+        .route("/api/users/:id/deactivate", post(deactivate_user))
+        .route("/api/users/:id/reactivate", post(reactivate_user))
+        .route(
+            "/api/storage/*key",
+            get(download_signed_object)
+                .put(upload_signed_object)
+                .layer(DefaultBodyLimit::max(MAX_SIGNED_UPLOAD_BYTES)),
+        )
         .layer(
             CorsLayer::new()
                 .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-                .allow_headers([CONTENT_TYPE])
+                .allow_headers([AUTHORIZATION, CONTENT_TYPE])
+                .expose_headers([
+                    HeaderName::from_static(IMAGE_WIDTH_HEADER),
+                    HeaderName::from_static(IMAGE_HEIGHT_HEADER),
+                ])
                 .allow_origin(Any),
         )
         .with_state(state);
 
-    println!("Router created. Starting lambda handler."); // <-- デバッグログ2
+    if is_running_on_lambda() {
+        println!("Router created. Starting lambda handler."); // <-- デバッグログ2
+        run_lambda(app).await
+    } else {
+        serve(app, &config).await
+    }
+}
+
+async fn connect_database(database: &DatabaseConfig) -> PgPool {
+    PgPoolOptions::new()
+        .max_connections(database.database_max_connections)
+        .connect(&database.database_url)
+        .await
+        .expect("Failed to create pool.")
+}
 
+// Lambda(API Gateway)経由でリクエストを処理する
+async fn run_lambda(app: Router) -> Result<(), Error> {
     run(service_fn(move |mut event: Request| {
         // REST(API GW v1) の stage を取得（/v1 を除去するため）
         let stage: Option<&str> = event
             .request_context_ref()
-            .and_then(|rc| match rc {
-                RequestContext::ApiGatewayV1(ctx) => ctx.stage.as_deref(), // ← ここを修正
+            .and_then(|context| match context {
+                RequestContext::ApiGatewayV1(ctx) => ctx.stage.as_deref(),
+                // lambda_http の apigw_http・alb などを有効にした場合の他のイベント（stage の除去は不要）
+                #[allow(unreachable_patterns)]
                 _ => None,
             });
 
@@ -116,6 +257,48 @@ async fn main() -> Result<(), Error> {
     .await
 }
 
+// Lambda以外（ローカル・コンテナ）ではTCPで待ち受ける通常のAxumサーバーとして起動する
+async fn serve(app: Router, config: &Config) -> Result<(), Error> {
+    let addr = format!("{}:{}", config.host, config.port);
+
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    println!("Router created. Listening on http://{}", listener.local_addr()?);
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    println!("Server shut down gracefully.");
+    Ok(())
+}
+
+// Ctrl+C または SIGTERM を受け取るまで待機する
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+
+    println!("Shutdown signal received, draining connections...");
+}
+
 fn is_running_on_lambda() -> bool {
     env::var("AWS_LAMETADATA_AWS_REQUEST_ID").is_ok() || env::var("AWS_LAMBDA_RUNTIME_API").is_ok()
 }
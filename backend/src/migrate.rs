@@ -0,0 +1,159 @@
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    Executor, PgPool,
+};
+
+// migrations/*.sql をバイナリに埋め込む（追加・変更時は build.rs により再ビルドされる）
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+// シードデータ（環境に依存しないスキーマ履歴とは別管理）
+const SEEDS: &[(&str, &str)] = &[("dev_users", include_str!("../seeds/dev_users.sql"))];
+
+// シードデータ・環境依存の修正を切り出す前のファイル内容のチェックサム(SHA-384)
+// これらで適用済みのDBは、内容変更扱いにせず新しいチェックサムに置き換える
+const LEGACY_CHECKSUMS: &[(i64, &str)] = &[
+    (1, "433cd2679b1b49aea00c06cc028c5b6b313f4e73b50f72eca2b6941a24458ab17c4ce41ea6a8b596f5426cf9199a7a5e"),
+    (3, "dee262df7f2b22a2258b5e55f3f17aabc6984b49c83a432e3fc8c91c2cc3c468a051519538e2b0f496eb20949ee5b60a"),
+];
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    // 適用後にファイルが書き換えられている
+    Modified,
+}
+
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+// 未適用のマイグレーションをすべて適用する
+pub async fn run(pool: &PgPool) -> Result<(), MigrateError> {
+    repair_legacy_checksums(pool).await?;
+    MIGRATOR.run(pool).await
+}
+
+// 各マイグレーションの適用状況を返す（DBへの書き込みは行わない）
+pub async fn status(pool: &PgPool) -> Result<Vec<MigrationStatus>, MigrateError> {
+    let applied = if migrations_table_exists(pool).await? {
+        let mut conn = pool.acquire().await?;
+        conn.list_applied_migrations().await?
+    } else {
+        Vec::new()
+    };
+
+    let statuses = MIGRATOR
+        .iter()
+        .filter(|m| m.migration_type.is_up_migration())
+        .map(|migration| {
+            let state = match applied.iter().find(|a| a.version == migration.version) {
+                None => MigrationState::Pending,
+                Some(a) if a.checksum == migration.checksum => MigrationState::Applied,
+                Some(a) if is_legacy_checksum(migration.version, &a.checksum) => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+            };
+            MigrationStatus {
+                version: migration.version,
+                description: migration.description.to_string(),
+                state,
+            }
+        })
+        .collect();
+
+    Ok(statuses)
+}
+
+pub async fn seed(pool: &PgPool) -> Result<(), sqlx::Error> {
+    for (name, sql) in SEEDS {
+        pool.execute(*sql).await?;
+        println!("Seeded {}", name);
+    }
+    Ok(())
+}
+
+// `migrate` サブコマンド: up(デフォルト) / status / dry-run / seed
+pub async fn run_cli(args: &[String], pool: &PgPool) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    match args.first().map(String::as_str).unwrap_or("up") {
+        "up" => {
+            let pending = count_pending(pool).await?;
+            run(pool).await?;
+            println!("Applied {} migration(s).", pending);
+        }
+        "status" => {
+            for s in status(pool).await? {
+                let label = match s.state {
+                    MigrationState::Applied => "applied",
+                    MigrationState::Pending => "pending",
+                    MigrationState::Modified => "MODIFIED",
+                };
+                println!("{:>4} {:<9} {}", s.version, label, s.description);
+            }
+        }
+        "dry-run" => {
+            let statuses = status(pool).await?;
+            let modified: Vec<_> = statuses.iter().filter(|s| s.state == MigrationState::Modified).collect();
+            for s in &modified {
+                println!("Migration {} ({}) was modified after it was applied; `up` will fail.", s.version, s.description);
+            }
+            let pending: Vec<_> = statuses.iter().filter(|s| s.state == MigrationState::Pending).collect();
+            if pending.is_empty() {
+                println!("Database is up to date.");
+            }
+            for s in pending {
+                println!("Would apply {} {}", s.version, s.description);
+            }
+        }
+        "seed" => seed(pool).await?,
+        other => {
+            return Err(format!(
+                "unknown migrate command: {} (expected up, status, dry-run or seed)",
+                other
+            )
+            .into())
+        }
+    }
+    Ok(())
+}
+
+async fn count_pending(pool: &PgPool) -> Result<usize, MigrateError> {
+    Ok(status(pool)
+        .await?
+        .iter()
+        .filter(|s| s.state == MigrationState::Pending)
+        .count())
+}
+
+async fn migrations_table_exists(pool: &PgPool) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL")
+        .fetch_one(pool)
+        .await
+}
+
+fn is_legacy_checksum(version: i64, checksum: &[u8]) -> bool {
+    LEGACY_CHECKSUMS
+        .iter()
+        .any(|(v, legacy)| *v == version && hex::decode(legacy).is_ok_and(|legacy| legacy == checksum))
+}
+
+async fn repair_legacy_checksums(pool: &PgPool) -> Result<(), MigrateError> {
+    if !migrations_table_exists(pool).await? {
+        return Ok(());
+    }
+
+    for (version, legacy) in LEGACY_CHECKSUMS {
+        let Some(migration) = MIGRATOR.iter().find(|m| m.version == *version) else {
+            continue;
+        };
+        let legacy = hex::decode(legacy).expect("legacy checksum is valid hex");
+        sqlx::query("UPDATE _sqlx_migrations SET checksum = $1 WHERE version = $2 AND checksum = $3")
+            .bind(migration.checksum.as_ref())
+            .bind(version)
+            .bind(legacy)
+            .execute(pool)
+            .await?;
+    }
+    Ok(())
+}
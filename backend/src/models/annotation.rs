@@ -75,3 +75,48 @@ pub struct UpdateAnnotationRequest {
 pub struct CreateAnnotationResponse {
     pub id: Uuid,
 }
+
+impl Annotation {
+    // [x, y, width, height]（bbox カラムが無い古いデータは x, y, width, height カラムから組み立てる）
+    pub fn bbox_xywh(&self) -> Option<[f32; 4]> {
+        match self.bbox.as_deref() {
+            Some([x, y, w, h, ..]) => Some([*x, *y, *w, *h]),
+            _ => Some([self.x?, self.y?, self.width?, self.height?]),
+        }
+    }
+
+    // bbox が無いポリゴンは頂点の外接矩形を使う
+    pub fn bounding_box(&self) -> Option<[f32; 4]> {
+        if let Some(bbox) = self.bbox_xywh() {
+            return Some(bbox);
+        }
+        let vertices = self.polygon()?;
+        let (min_x, max_x, min_y, max_y) = vertices.iter().fold(
+            (f32::MAX, f32::MIN, f32::MAX, f32::MIN),
+            |(min_x, max_x, min_y, max_y), [x, y]| (min_x.min(*x), max_x.max(*x), min_y.min(*y), max_y.max(*y)),
+        );
+        Some([min_x, min_y, max_x - min_x, max_y - min_y])
+    }
+
+    // ポリゴンの頂点（[[x, y], ...] または [{"x": .., "y": ..}, ...] 形式）
+    // 頂点が3つ未満の場合は None を返す
+    pub fn polygon(&self) -> Option<Vec<[f32; 2]>> {
+        let points = self.points.as_ref()?.as_array()?;
+        let vertices = points
+            .iter()
+            .map(|point| {
+                let (x, y) = match point {
+                    serde_json::Value::Array(xy) if xy.len() >= 2 => (xy[0].as_f64()?, xy[1].as_f64()?),
+                    serde_json::Value::Object(xy) => (xy.get("x")?.as_f64()?, xy.get("y")?.as_f64()?),
+                    _ => return None,
+                };
+                Some([x as f32, y as f32])
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        if vertices.len() < 3 {
+            return None;
+        }
+        Some(vertices)
+    }
+}
@@ -1,12 +1,19 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use super::label::nullable;
+
 // main.rsからDatasetFormatを移動
-#[derive(Debug, Serialize, Deserialize, Clone, Copy, sqlx::Type)]
-#[sqlx(type_name = "dataset_format")]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "dataset_format", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum DatasetFormat {
     Yolo,
+    // ポリゴンを書き出す Ultralytics のセグメンテーション形式
+    #[sqlx(rename = "yolo-seg")]
+    #[serde(rename = "yolo-seg")]
+    YoloSeg,
     Coco,
     Voc
 }
@@ -16,12 +23,25 @@ impl std::fmt::Display for DatasetFormat {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             DatasetFormat::Yolo => write!(f, "yolo"),
+            DatasetFormat::YoloSeg => write!(f, "yolo-seg"),
             DatasetFormat::Coco => write!(f, "coco"),
             DatasetFormat::Voc => write!(f, "voc"),
         }
     }
 }
 
+// データセットの状態
+// locked・published のデータセットに含まれる画像は、force を指定しない限り削除できない
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "dataset_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetStatus {
+    #[default]
+    Draft,
+    Locked,
+    Published,
+}
+
 // CreateDatasetRequestのモデル
 #[derive(Debug, Deserialize)]
 pub struct CreateDatasetRequest {
@@ -37,6 +57,67 @@ pub struct CreateDatasetResponse {
     pub id: Uuid,
     pub name: String,
     pub format: DatasetFormat,
-    pub download_url: String,
+    // 作成と同時に登録したエクスポートジョブ
+    pub export_job_id: Uuid,
+    // エクスポートの完了後に成果物へリダイレクトするURL（完了前は 409 を返す）
+    pub download_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Dataset {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub format: DatasetFormat,
+    pub status: DatasetStatus,
+    // 削除済みの画像は含まない
+    pub image_count: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatasetDetail {
+    #[serde(flatten)]
+    pub dataset: Dataset,
+    pub image_ids: Vec<Uuid>,
 }
 
+// 名前・説明・状態の変更（指定したフィールドのみ更新する）
+// description は null を指定すると説明を消す
+#[derive(Debug, Deserialize)]
+pub struct UpdateDatasetRequest {
+    pub name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub description: Option<Option<String>>,
+    pub status: Option<DatasetStatus>,
+}
+
+// データセットへの画像の追加・削除
+#[derive(Debug, Deserialize)]
+pub struct DatasetImagesRequest {
+    pub image_ids: Vec<Uuid>,
+}
+
+
+// エクスポート時の分割
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
+#[sqlx(type_name = "dataset_split", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetSplit {
+    Train,
+    Val,
+    Test,
+}
+
+impl DatasetSplit {
+    pub const ALL: [DatasetSplit; 3] = [DatasetSplit::Train, DatasetSplit::Val, DatasetSplit::Test];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DatasetSplit::Train => "train",
+            DatasetSplit::Val => "val",
+            DatasetSplit::Test => "test",
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use uuid::Uuid;
+
+use super::DatasetFormat;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "export_job_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ExportJobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportJob {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub dataset_id: Option<Uuid>,
+    pub name: String,
+    pub format: DatasetFormat,
+    pub status: ExportJobStatus,
+    // 取得済みの画像数 / 対象の画像数（対象が決まるまでは None）
+    pub processed_images: i32,
+    pub total_images: Option<i32>,
+    #[serde(skip)]
+    pub object_key: Option<String>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportJobResponse {
+    #[serde(flatten)]
+    pub job: ExportJob,
+    // 完了したジョブのみ、成果物の期限付きダウンロードURL
+    pub download_url: Option<String>,
+}
@@ -20,6 +20,156 @@ pub struct Image {
     pub vector: Option<serde_json::Value>,
 }
 
+// 一覧で返す画像の情報（vector を除く）
+#[derive(Debug, Serialize, FromRow)]
+pub struct ImageSummary {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub filename: String,
+    pub original_filename: String,
+    pub s3_bucket: String,
+    pub s3_key: String,
+    pub file_size: i64,
+    pub width: i32,
+    pub height: i32,
+    pub format: String,
+    pub classification_label: Option<String>,
+    pub created_at: DateTime<Utc>,
+    // 論理削除した日時（削除されていない画像は None）
+    pub deleted_at: Option<DateTime<Utc>>,
+    // EXIF から読み取ったメタデータ（ImageMetadata）
+    pub metadata: Option<serde_json::Value>,
+}
+
+// GET /api/images/:id?size= で返す画像の大きさ
+// リサイズ版は表示用で、アノテーションの座標は常に元画像（width・height）の座標系
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageRendition {
+    #[default]
+    Original,
+    // 長辺 256px のサムネイル
+    Thumb,
+    // 長辺 1600px のプレビュー
+    Preview,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GetImageQuery {
+    #[serde(default)]
+    pub size: ImageRendition,
+}
+
+// 取り込み時に EXIF から読み取るメタデータ（images.metadata）
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ImageMetadata {
+    // 撮影日時。EXIF にタイムゾーンが無い場合は撮影時の現地時刻（オフセットなし）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taken_at: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_make: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub camera_model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gps: Option<GpsPosition>,
+    // EXIF の Orientation（1〜8）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub orientation: Option<u16>,
+    // orientation の回転・反転を画素に反映して保存したか（BAKE_IMAGE_ORIENTATION）
+    #[serde(default)]
+    pub orientation_applied: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpsPosition {
+    pub latitude: f64,
+    pub longitude: f64,
+    // 海抜（メートル）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub altitude: Option<f64>,
+}
+
+// 画像一覧の並べ替えキー（同じ値の画像は ID の順）
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageSort {
+    #[default]
+    CreatedAt,
+    // 元のファイル名
+    Filename,
+    FileSize,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// 画像一覧の絞り込み・並べ替え・ページング
+// cursor には前のページの next_cursor を渡す（sort・order は前のページと同じにする）
+#[derive(Debug, Deserialize)]
+pub struct ListImagesQuery {
+    pub cursor: Option<String>,
+    pub limit: Option<i64>,
+    // アップロードしたユーザー
+    pub user_id: Option<Uuid>,
+    // created_after <= created_at < created_before
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub format: Option<String>,
+    pub classification_label: Option<String>,
+    // このデータセットに含まれる画像
+    pub dataset_id: Option<Uuid>,
+    // true はアノテーションのある画像、false は無い画像
+    pub annotated: Option<bool>,
+    // このラベルのアノテーションがある画像
+    pub label: Option<String>,
+    // true の場合は論理削除した（復元できる）画像のみを返す
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub sort: ImageSort,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImageListResponse {
+    pub images: Vec<ImageSummary>,
+    // 次のページが無い場合は None
+    pub next_cursor: Option<String>,
+}
+
+// 画像の削除オプション
+// force: locked・published のデータセットに含まれる画像も削除する
+// permanent: 復元期間を待たずに、ストレージのオブジェクトとともに完全に削除する
+#[derive(Debug, Default, Deserialize)]
+pub struct DeleteImageOptions {
+    #[serde(default)]
+    pub force: bool,
+    #[serde(default)]
+    pub permanent: bool,
+}
+
+// 画像の一括削除（一部でも削除できない画像があれば、どの画像も削除しない）
+#[derive(Debug, Deserialize)]
+pub struct DeleteImagesRequest {
+    pub image_ids: Vec<Uuid>,
+    #[serde(flatten)]
+    pub options: DeleteImageOptions,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeleteImagesResponse {
+    pub image_ids: Vec<Uuid>,
+    pub permanent: bool,
+    // 論理削除した画像を復元できる期限（複数の画像で異なる場合は最も早い期限）
+    pub restorable_until: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct CreateImageRequest {
     pub filename: String,
@@ -35,9 +185,31 @@ pub struct CreateImageRequest {
 pub struct ImageResponse {
     pub id: Uuid,
     pub s3_key: String,
+    pub filename: String,
+    pub file_size: i64,
+    pub width: i32,
+    pub height: i32,
+    pub format: String,
+    pub content_hash: String,
+    pub metadata: ImageMetadata,
     pub created_at: DateTime<Utc>,
 }
 
+// multipart アップロードのファイルごとの結果（image・error のどちらか一方）
+#[derive(Debug, Serialize)]
+pub struct UploadImageResult {
+    pub filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UploadImagesResponse {
+    pub results: Vec<UploadImageResult>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ImageSearchRequest {
     pub query: String,
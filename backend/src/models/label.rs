@@ -0,0 +1,167 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::FromRow;
+use std::collections::HashMap;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+// ラベル（クラス）
+// id は class_ids・filter.labels を指定しないエクスポートのクラスID（COCO のカテゴリIDは id + 1）
+// name はプロジェクトごとに一意（project_id が NULL のラベルはすべてのプロジェクトで共有する）
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Label {
+    pub id: i32,
+    pub project_id: Option<Uuid>,
+    pub name: String,
+    pub color: String,
+    pub description: Option<String>,
+    pub parent_id: Option<i32>,
+    pub is_archived: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateLabelRequest {
+    #[validate(length(min = 1, max = 100), custom = "validate_name")]
+    pub name: String,
+    // 省略した場合は #ff0000
+    #[validate(custom = "validate_color")]
+    pub color: Option<String>,
+    pub description: Option<String>,
+    pub parent_id: Option<i32>,
+    // 省略した場合はすべてのプロジェクトで共有するラベル
+    pub project_id: Option<Uuid>,
+}
+
+// 名前はアノテーションから参照されているため、ここでは変更できない
+// 省略した項目は変更しない（parent_id は null を指定すると親を外す）
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateLabelRequest {
+    #[validate(custom = "validate_color")]
+    pub color: Option<String>,
+    pub description: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub parent_id: Option<Option<i32>>,
+    pub is_archived: Option<bool>,
+}
+
+// 空白のみの名前は使えない
+fn validate_name(name: &str) -> Result<(), ValidationError> {
+    if name.trim().is_empty() {
+        return Err(ValidationError::new("name must not be blank"));
+    }
+    Ok(())
+}
+
+// 表示色は #rrggbb 形式
+fn validate_color(color: &str) -> Result<(), ValidationError> {
+    match color.strip_prefix('#') {
+        Some(hex) if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) => Ok(()),
+        _ => Err(ValidationError::new("color must be in #rrggbb format")),
+    }
+}
+
+// 省略（None）と null（Some(None)）を区別する
+pub(crate) fn nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// ラベル名の変更（アノテーションのラベルもすべて変更する）
+#[derive(Debug, Deserialize, Validate)]
+pub struct RenameLabelRequest {
+    #[validate(length(min = 1, max = 100), custom = "validate_name")]
+    pub name: String,
+    // true の場合は変更せず、変更されるアノテーション数のみ返す
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// source_ids のラベルを target_id のラベルに統合し、統合元のラベルは削除する
+#[derive(Debug, Deserialize, Validate)]
+pub struct MergeLabelsRequest {
+    #[validate(length(min = 1))]
+    pub source_ids: Vec<i32>,
+    pub target_id: i32,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+// 名前変更・統合の結果（dry_run の場合は実行した場合の結果）
+#[derive(Debug, Serialize)]
+pub struct LabelChangeResponse {
+    pub dry_run: bool,
+    // 変更後のラベル
+    pub label: Label,
+    pub annotations_changed: i64,
+    // 変更前のラベルごとの変更されたアノテーション数
+    pub annotations_by_label: HashMap<String, i64>,
+    // dry_run の場合は None
+    pub audit_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[sqlx(type_name = "label_audit_action", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum LabelAuditAction {
+    Rename,
+    Merge,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LabelAuditLog {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub action: LabelAuditAction,
+    pub source_labels: Vec<String>,
+    pub target_label: String,
+    pub target_label_id: Option<i32>,
+    pub annotations_changed: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListLabelsQuery {
+    // true の場合はアーカイブしたラベルも返す
+    #[serde(default)]
+    pub include_archived: bool,
+    // 指定した場合はそのプロジェクトのラベルと共有ラベルのみ返す
+    pub project_id: Option<Uuid>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rename(name: &str) -> RenameLabelRequest {
+        RenameLabelRequest { name: name.to_string(), dry_run: false }
+    }
+
+    #[test]
+    fn label_names_must_not_be_blank_or_too_long() {
+        assert!(rename("car").validate().is_ok());
+        assert!(rename("").validate().is_err());
+        assert!(rename("   ").validate().is_err());
+        assert!(rename(&"a".repeat(101)).validate().is_err());
+
+        let create = CreateLabelRequest {
+            name: " \t".to_string(),
+            color: None,
+            description: None,
+            parent_id: None,
+            project_id: None,
+        };
+        assert!(create.validate().is_err());
+    }
+
+    #[test]
+    fn merge_requires_source_ids() {
+        let merge = |source_ids: Vec<i32>| MergeLabelsRequest { source_ids, target_id: 0, dry_run: false };
+        assert!(merge(vec![]).validate().is_err());
+        assert!(merge(vec![1]).validate().is_ok());
+    }
+}
@@ -1,8 +1,14 @@
 pub mod annotation;
 pub mod dataset;
+pub mod export_job;
 pub mod image;
+pub mod label;
+pub mod user;
 
 // 各モジュールから主要な型を再エクスポート
 pub use annotation::*;
 pub use dataset::*;
+pub use export_job::*;
 pub use image::*;
+pub use label::*;
+pub use user::*;
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
+use validator::Validate;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct User {
@@ -10,30 +11,56 @@ pub struct User {
     pub username: String,
     pub email: String,
     pub role: UserRole,
+    pub is_active: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "user_role", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
 pub enum UserRole {
     Admin,
     Annotator,
     Viewer,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateUserRequest {
+    #[validate(length(min = 1))]
     pub cognito_sub: String,
+    #[validate(length(min = 1))]
     pub username: String,
+    #[validate(email)]
     pub email: String,
     pub role: UserRole,
 }
 
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateUserRoleRequest {
+    pub role: UserRole,
+}
+
+// ユーザー一覧のページング（page は1始まり）
+#[derive(Debug, Deserialize)]
+pub struct ListUsersQuery {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
 #[derive(Debug, Serialize)]
+pub struct UserListResponse {
+    pub users: Vec<User>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct UserInfo {
     pub id: Uuid,
     pub username: String,
     pub email: String,
     pub role: UserRole,
+    pub is_active: bool,
 }
@@ -0,0 +1,277 @@
+use axum::async_trait;
+use bytes::{Bytes, BytesMut};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha2::Sha256;
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use super::{
+    validate_key, ByteRange, ObjectInfo, ObjectStore, ObjectStream, ObjectUpload, SignedMethod, StorageError,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+// get_stream で一度に読み出す大きさ
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+// キーの各セグメントをURLパスに埋め込む際にエスケープしない文字
+const KEY_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+// ローカルディスクにオブジェクトを保存する実装
+// 署名付きURLはバックエンド自身の /api/storage/*key を指し、HMACで検証する
+pub struct LocalStore {
+    root: PathBuf,
+    public_base_url: String,
+    signing_secret: Vec<u8>,
+}
+
+impl LocalStore {
+    pub fn new(root: PathBuf, public_base_url: String, signing_secret: String) -> Self {
+        Self {
+            root,
+            public_base_url: public_base_url.trim_end_matches('/').to_string(),
+            signing_secret: signing_secret.into_bytes(),
+        }
+    }
+
+    fn path_for(&self, key: &str) -> Result<PathBuf, StorageError> {
+        validate_key(key)?;
+        Ok(self.root.join(key))
+    }
+
+    fn mac(&self, method: SignedMethod, key: &str, expires: i64) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.signing_secret)
+            .expect("HMAC accepts keys of any length");
+        mac.update(format!("{}\n{}\n{}", method.as_str(), key, expires).as_bytes());
+        mac
+    }
+}
+
+fn io_error(key: &str, e: std::io::Error) -> StorageError {
+    if e.kind() == ErrorKind::NotFound {
+        StorageError::NotFound(key.to_string())
+    } else {
+        StorageError::Backend(e.to_string())
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalStore {
+    fn bucket(&self) -> &str {
+        "local"
+    }
+
+    async fn put(&self, key: &str, data: Bytes, _content_type: &str) -> Result<(), StorageError> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| io_error(key, e))?;
+        }
+
+        // 書き込み途中のファイルを読まれないよう、一時ファイルに書いてから置き換える
+        let tmp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+        tokio::fs::write(&tmp_path, &data)
+            .await
+            .map_err(|e| io_error(key, e))?;
+        tokio::fs::rename(&tmp_path, &path)
+            .await
+            .map_err(|e| io_error(key, e))
+    }
+
+    async fn start_upload(&self, key: &str, _content_type: &str) -> Result<Box<dyn ObjectUpload>, StorageError> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| io_error(key, e))?;
+        }
+
+        // put と同様、一時ファイルに書いてから finish で置き換える
+        let tmp_path = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4().simple()));
+        let file = tokio::fs::File::create(&tmp_path)
+            .await
+            .map_err(|e| io_error(key, e))?;
+
+        Ok(Box::new(LocalUpload {
+            key: key.to_string(),
+            path,
+            tmp_path,
+            file,
+        }))
+    }
+
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError> {
+        let path = self.path_for(key)?;
+        tokio::fs::read(&path)
+            .await
+            .map(Bytes::from)
+            .map_err(|e| io_error(key, e))
+    }
+
+    async fn get_stream(&self, key: &str, range: Option<ByteRange>) -> Result<ObjectStream, StorageError> {
+        let path = self.path_for(key)?;
+        let mut file = tokio::fs::File::open(&path).await.map_err(|e| io_error(key, e))?;
+        let limit = match range {
+            Some(range) => {
+                file.seek(std::io::SeekFrom::Start(range.start))
+                    .await
+                    .map_err(|e| io_error(key, e))?;
+                range.end - range.start + 1
+            }
+            None => u64::MAX,
+        };
+
+        let key = key.to_string();
+        Ok(Box::pin(futures::stream::try_unfold(
+            (file.take(limit), key),
+            |(mut reader, key)| async move {
+                let mut chunk = BytesMut::with_capacity(READ_CHUNK_SIZE);
+                let read = reader.read_buf(&mut chunk).await.map_err(|e| io_error(&key, e))?;
+                Ok((read > 0).then(|| (chunk.freeze(), (reader, key))))
+            },
+        )))
+    }
+
+    async fn head(&self, key: &str) -> Result<ObjectInfo, StorageError> {
+        let path = self.path_for(key)?;
+        let metadata = tokio::fs::metadata(&path).await.map_err(|e| io_error(key, e))?;
+        if !metadata.is_file() {
+            return Err(StorageError::NotFound(key.to_string()));
+        }
+        Ok(ObjectInfo {
+            key: key.to_string(),
+            size: metadata.len() as i64,
+        })
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        let path = self.path_for(key)?;
+        match tokio::fs::remove_file(&path).await {
+            Ok(()) => Ok(()),
+            // S3と同様、存在しないキーの削除は成功扱い
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(io_error(key, e)),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectInfo>, StorageError> {
+        let mut objects = Vec::new();
+        let mut pending: Vec<PathBuf> = vec![self.root.clone()];
+
+        while let Some(dir) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                Err(e) => return Err(StorageError::Backend(e.to_string())),
+            };
+
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| StorageError::Backend(e.to_string()))?
+            {
+                let path = entry.path();
+                let metadata = entry
+                    .metadata()
+                    .await
+                    .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+                if metadata.is_dir() {
+                    pending.push(path);
+                    continue;
+                }
+
+                if let Some(key) = relative_key(&self.root, &path) {
+                    if key.starts_with(prefix) && !key.ends_with(".tmp") {
+                        objects.push(ObjectInfo {
+                            key,
+                            size: metadata.len() as i64,
+                        });
+                    }
+                }
+            }
+        }
+
+        objects.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(objects)
+    }
+
+    async fn signed_url(
+        &self,
+        method: SignedMethod,
+        key: &str,
+        expires_in: Duration,
+    ) -> Result<String, StorageError> {
+        validate_key(key)?;
+        let expires = Utc::now().timestamp() + expires_in.as_secs() as i64;
+        let signature = hex::encode(self.mac(method, key, expires).finalize().into_bytes());
+
+        let encoded_key = key
+            .split('/')
+            .map(|segment| utf8_percent_encode(segment, KEY_SEGMENT).to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Ok(format!(
+            "{}/api/storage/{}?expires={}&signature={}",
+            self.public_base_url, encoded_key, expires, signature
+        ))
+    }
+
+    fn verify_signed_url(&self, method: SignedMethod, key: &str, expires: i64, signature: &str) -> bool {
+        if expires < Utc::now().timestamp() {
+            return false;
+        }
+        let Ok(signature) = hex::decode(signature) else {
+            return false;
+        };
+        self.mac(method, key, expires).verify_slice(&signature).is_ok()
+    }
+}
+
+struct LocalUpload {
+    key: String,
+    path: PathBuf,
+    tmp_path: PathBuf,
+    file: tokio::fs::File,
+}
+
+#[async_trait]
+impl ObjectUpload for LocalUpload {
+    async fn write(&mut self, chunk: Bytes) -> Result<(), StorageError> {
+        self.file
+            .write_all(&chunk)
+            .await
+            .map_err(|e| io_error(&self.key, e))
+    }
+
+    async fn finish(mut self: Box<Self>) -> Result<(), StorageError> {
+        self.file.flush().await.map_err(|e| io_error(&self.key, e))?;
+        tokio::fs::rename(&self.tmp_path, &self.path)
+            .await
+            .map_err(|e| io_error(&self.key, e))
+    }
+
+    async fn abort(self: Box<Self>) -> Result<(), StorageError> {
+        let LocalUpload { key, tmp_path, file, .. } = *self;
+        drop(file);
+        match tokio::fs::remove_file(&tmp_path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(io_error(&key, e)),
+        }
+    }
+}
+
+fn relative_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let segments: Option<Vec<&str>> = relative.components().map(|c| c.as_os_str().to_str()).collect();
+    Some(segments?.join("/"))
+}
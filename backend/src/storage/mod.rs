@@ -0,0 +1,123 @@
+pub mod local;
+pub mod s3;
+
+use axum::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use std::{fmt, time::Duration};
+
+pub use local::LocalStore;
+pub use s3::S3Store;
+
+// 署名付きURLで許可する操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignedMethod {
+    Get,
+    Put,
+}
+
+impl SignedMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignedMethod::Get => "GET",
+            SignedMethod::Put => "PUT",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjectInfo {
+    pub key: String,
+    pub size: i64,
+}
+
+// オブジェクトの一部分（start・end はどちらも含むバイト位置）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+// 先頭から順に読み出すオブジェクトの内容
+pub type ObjectStream = BoxStream<'static, Result<Bytes, StorageError>>;
+
+#[derive(Debug)]
+pub enum StorageError {
+    NotFound(String),
+    InvalidKey(String),
+    Backend(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::NotFound(key) => write!(f, "object not found: {}", key),
+            StorageError::InvalidKey(key) => write!(f, "invalid object key: {}", key),
+            StorageError::Backend(message) => write!(f, "storage backend error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+// 画像やエクスポート成果物を保存するオブジェクトストレージの抽象
+// S3 と ローカルディスク の実装があり、AppState からは trait object として扱う
+#[async_trait]
+pub trait ObjectStore: Send + Sync {
+    // images.s3_bucket に記録する保存先の名前
+    fn bucket(&self) -> &str;
+
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> Result<(), StorageError>;
+
+    // 全体をメモリに載せずに、先頭から順に書き込む（エクスポートの成果物など）
+    async fn start_upload(&self, key: &str, content_type: &str) -> Result<Box<dyn ObjectUpload>, StorageError>;
+
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError>;
+
+    // 全体をメモリに載せずに、range の範囲（None の場合は全体）を読み出す
+    async fn get_stream(&self, key: &str, range: Option<ByteRange>) -> Result<ObjectStream, StorageError>;
+
+    // 本体を読まずにオブジェクトの存在と大きさを確認する
+    async fn head(&self, key: &str) -> Result<ObjectInfo, StorageError>;
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectInfo>, StorageError>;
+
+    // クライアントが直接アップロード・ダウンロードするための期限付きURL
+    async fn signed_url(
+        &self,
+        method: SignedMethod,
+        key: &str,
+        expires_in: Duration,
+    ) -> Result<String, StorageError>;
+
+    // バックエンド自身が署名付きURLを配信する実装のみ true を返す
+    fn verify_signed_url(&self, _method: SignedMethod, _key: &str, _expires: i64, _signature: &str) -> bool {
+        false
+    }
+}
+
+// start_upload で開始した書き込み
+// finish を呼ぶまではオブジェクトとして見えず、abort で書き込んだ内容を破棄する
+#[async_trait]
+pub trait ObjectUpload: Send {
+    async fn write(&mut self, chunk: Bytes) -> Result<(), StorageError>;
+
+    async fn finish(self: Box<Self>) -> Result<(), StorageError>;
+
+    async fn abort(self: Box<Self>) -> Result<(), StorageError>;
+}
+
+// パストラバーサルを防ぐため、キーの形式を検証する
+pub fn validate_key(key: &str) -> Result<(), StorageError> {
+    let invalid = key.is_empty()
+        || key.starts_with('/')
+        || key.contains('\\')
+        || key.split('/').any(|segment| segment.is_empty() || segment == "." || segment == "..");
+
+    if invalid {
+        return Err(StorageError::InvalidKey(key.to_string()));
+    }
+    Ok(())
+}
@@ -0,0 +1,279 @@
+use aws_sdk_s3::{
+    error::SdkError,
+    operation::get_object::GetObjectError,
+    presigning::PresigningConfig,
+    types::{CompletedMultipartUpload, CompletedPart},
+    Client as S3Client,
+};
+use axum::async_trait;
+use bytes::{Bytes, BytesMut};
+use std::time::Duration;
+
+use super::{ByteRange, ObjectInfo, ObjectStore, ObjectStream, ObjectUpload, SignedMethod, StorageError};
+
+// マルチパートアップロードの1パートの大きさ（S3 の下限は最後のパートを除き 5MiB）
+const PART_SIZE: usize = 8 * 1024 * 1024;
+
+pub struct S3Store {
+    client: S3Client,
+    bucket: String,
+}
+
+impl S3Store {
+    pub fn new(client: S3Client, bucket: String) -> Self {
+        Self { client, bucket }
+    }
+}
+
+fn backend_error(e: impl std::fmt::Display) -> StorageError {
+    StorageError::Backend(e.to_string())
+}
+
+fn get_object_error<R: std::fmt::Debug>(key: &str, e: SdkError<GetObjectError, R>) -> StorageError {
+    if e.as_service_error().is_some_and(|se| se.is_no_such_key()) {
+        StorageError::NotFound(key.to_string())
+    } else {
+        backend_error(e)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for S3Store {
+    fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    async fn put(&self, key: &str, data: Bytes, content_type: &str) -> Result<(), StorageError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .body(data.into())
+            .content_type(content_type)
+            .send()
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn start_upload(&self, key: &str, content_type: &str) -> Result<Box<dyn ObjectUpload>, StorageError> {
+        let output = self
+            .client
+            .create_multipart_upload()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .send()
+            .await
+            .map_err(backend_error)?;
+        let upload_id = output
+            .upload_id()
+            .ok_or_else(|| backend_error("S3 did not return an upload id"))?
+            .to_string();
+
+        Ok(Box::new(S3Upload {
+            client: self.client.clone(),
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            upload_id,
+            buffer: BytesMut::new(),
+            parts: Vec::new(),
+        }))
+    }
+
+    async fn get(&self, key: &str) -> Result<Bytes, StorageError> {
+        let output = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| get_object_error(key, e))?;
+
+        let data = output.body.collect().await.map_err(backend_error)?;
+        Ok(data.into_bytes())
+    }
+
+    async fn get_stream(&self, key: &str, range: Option<ByteRange>) -> Result<ObjectStream, StorageError> {
+        let mut request = self.client.get_object().bucket(&self.bucket).key(key);
+        if let Some(range) = range {
+            request = request.range(format!("bytes={}-{}", range.start, range.end));
+        }
+        let output = request.send().await.map_err(|e| get_object_error(key, e))?;
+
+        Ok(Box::pin(futures::stream::try_unfold(output.body, |mut body| async move {
+            let chunk = body.try_next().await.map_err(backend_error)?;
+            Ok(chunk.map(|chunk| (chunk, body)))
+        })))
+    }
+
+    async fn head(&self, key: &str) -> Result<ObjectInfo, StorageError> {
+        let output = self
+            .client
+            .head_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(|e| {
+                if e.as_service_error().is_some_and(|se| se.is_not_found()) {
+                    StorageError::NotFound(key.to_string())
+                } else {
+                    backend_error(e)
+                }
+            })?;
+
+        Ok(ObjectInfo {
+            key: key.to_string(),
+            size: output.content_length().unwrap_or(0),
+        })
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        self.client
+            .delete_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .send()
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<ObjectInfo>, StorageError> {
+        let mut pages = self
+            .client
+            .list_objects_v2()
+            .bucket(&self.bucket)
+            .prefix(prefix)
+            .into_paginator()
+            .send();
+
+        let mut objects = Vec::new();
+        while let Some(page) = pages.next().await {
+            let page = page.map_err(backend_error)?;
+            for object in page.contents() {
+                if let Some(key) = object.key() {
+                    objects.push(ObjectInfo {
+                        key: key.to_string(),
+                        size: object.size().unwrap_or(0),
+                    });
+                }
+            }
+        }
+        Ok(objects)
+    }
+
+    async fn signed_url(
+        &self,
+        method: SignedMethod,
+        key: &str,
+        expires_in: Duration,
+    ) -> Result<String, StorageError> {
+        let presigning_config = PresigningConfig::expires_in(expires_in).map_err(backend_error)?;
+
+        let presigned_request = match method {
+            SignedMethod::Get => self
+                .client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .presigned(presigning_config)
+                .await
+                .map_err(backend_error)?,
+            SignedMethod::Put => self
+                .client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .presigned(presigning_config)
+                .await
+                .map_err(backend_error)?,
+        };
+
+        Ok(presigned_request.uri().to_string())
+    }
+}
+
+struct S3Upload {
+    client: S3Client,
+    bucket: String,
+    key: String,
+    upload_id: String,
+    // PART_SIZE に満たない、まだ送っていないデータ
+    buffer: BytesMut,
+    parts: Vec<CompletedPart>,
+}
+
+impl S3Upload {
+    async fn upload_part(&mut self, data: Bytes) -> Result<(), StorageError> {
+        let part_number = self.parts.len() as i32 + 1;
+        let output = self
+            .client
+            .upload_part()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(&self.upload_id)
+            .part_number(part_number)
+            .body(data.into())
+            .send()
+            .await
+            .map_err(backend_error)?;
+
+        self.parts.push(
+            CompletedPart::builder()
+                .part_number(part_number)
+                .set_e_tag(output.e_tag().map(str::to_string))
+                .build(),
+        );
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ObjectUpload for S3Upload {
+    async fn write(&mut self, chunk: Bytes) -> Result<(), StorageError> {
+        self.buffer.extend_from_slice(&chunk);
+        while self.buffer.len() >= PART_SIZE {
+            let part = self.buffer.split_to(PART_SIZE).freeze();
+            self.upload_part(part).await?;
+        }
+        Ok(())
+    }
+
+    async fn finish(mut self: Box<Self>) -> Result<(), StorageError> {
+        // 最後のパートは 5MiB 未満でもよい（ただし空のアップロードでも1パートは必要）
+        if !self.buffer.is_empty() || self.parts.is_empty() {
+            let part = self.buffer.split().freeze();
+            self.upload_part(part).await?;
+        }
+
+        self.client
+            .complete_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(&self.upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(std::mem::take(&mut self.parts)))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) -> Result<(), StorageError> {
+        self.client
+            .abort_multipart_upload()
+            .bucket(&self.bucket)
+            .key(&self.key)
+            .upload_id(&self.upload_id)
+            .send()
+            .await
+            .map_err(backend_error)?;
+        Ok(())
+    }
+}
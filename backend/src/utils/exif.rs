@@ -0,0 +1,119 @@
+use chrono::{FixedOffset, NaiveDate};
+use exif::{DateTime, Exif, Field, In, Reader, Tag, Value};
+use image::DynamicImage;
+use std::io::Cursor;
+
+use crate::models::{GpsPosition, ImageMetadata};
+
+// 画像の EXIF からメタデータを読み取る（EXIF が無い・読めない場合は空）
+pub fn read_metadata(data: &[u8]) -> ImageMetadata {
+    let exif = match Reader::new().read_from_container(&mut Cursor::new(data)) {
+        Ok(exif) => exif,
+        Err(_) => return ImageMetadata::default(),
+    };
+
+    ImageMetadata {
+        taken_at: taken_at(&exif),
+        camera_make: text(&exif, Tag::Make),
+        camera_model: text(&exif, Tag::Model),
+        gps: gps_position(&exif),
+        orientation: field(&exif, Tag::Orientation)
+            .and_then(|field| field.value.get_uint(0))
+            .filter(|orientation| (1..=8).contains(orientation))
+            .map(|orientation| orientation as u16),
+        orientation_applied: false,
+    }
+}
+
+// 90度回転を含む向き（5〜8）では、表示される幅と高さが保存された画素と入れ替わる
+pub fn swaps_dimensions(orientation: u16) -> bool {
+    (5..=8).contains(&orientation)
+}
+
+// EXIF の Orientation に従って回転・反転し、表示される向きの画素にする
+pub fn apply_orientation(image: DynamicImage, orientation: u16) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+fn field(exif: &Exif, tag: Tag) -> Option<&Field> {
+    exif.get_field(tag, In::PRIMARY)
+}
+
+fn ascii(exif: &Exif, tag: Tag) -> Option<&[u8]> {
+    match &field(exif, tag)?.value {
+        Value::Ascii(values) => values.first().map(Vec::as_slice),
+        _ => None,
+    }
+}
+
+fn text(exif: &Exif, tag: Tag) -> Option<String> {
+    let value = String::from_utf8_lossy(ascii(exif, tag)?);
+    let value = value.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+// 撮影日時（DateTimeOriginal、無ければ DateTime）
+fn taken_at(exif: &Exif) -> Option<String> {
+    let raw = ascii(exif, Tag::DateTimeOriginal).or_else(|| ascii(exif, Tag::DateTime))?;
+    let mut datetime = DateTime::from_ascii(raw).ok()?;
+    if let Some(offset) = ascii(exif, Tag::OffsetTimeOriginal) {
+        // オフセットが壊れている場合は現地時刻として扱う
+        let _ = datetime.parse_offset(offset);
+    }
+
+    let naive = NaiveDate::from_ymd_opt(datetime.year.into(), datetime.month.into(), datetime.day.into())?
+        .and_hms_nano_opt(
+            datetime.hour.into(),
+            datetime.minute.into(),
+            datetime.second.into(),
+            datetime.nanosecond.unwrap_or(0),
+        )?;
+
+    match datetime.offset.and_then(|minutes| FixedOffset::east_opt(i32::from(minutes) * 60)) {
+        Some(offset) => Some(naive.and_local_timezone(offset).single()?.to_rfc3339()),
+        None => Some(naive.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+    }
+}
+
+fn gps_position(exif: &Exif) -> Option<GpsPosition> {
+    let latitude = gps_coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, b'S')?;
+    let longitude = gps_coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, b'W')?;
+
+    let altitude = match field(exif, Tag::GPSAltitude).map(|field| &field.value) {
+        Some(Value::Rational(values)) => values.first().map(|value| value.to_f64()).filter(|value| value.is_finite()),
+        _ => None,
+    };
+    // GPSAltitudeRef が 1 の場合は海面下
+    let below_sea_level = field(exif, Tag::GPSAltitudeRef).and_then(|field| field.value.get_uint(0)) == Some(1);
+
+    Some(GpsPosition {
+        latitude,
+        longitude,
+        altitude: altitude.map(|altitude| if below_sea_level { -altitude } else { altitude }),
+    })
+}
+
+// 度・分・秒の3つの有理数を10進の度にする（南緯・西経は負）
+fn gps_coordinate(exif: &Exif, tag: Tag, ref_tag: Tag, negative_ref: u8) -> Option<f64> {
+    let degrees = match &field(exif, tag)?.value {
+        Value::Rational(values) if values.len() >= 3 => {
+            values[0].to_f64() + values[1].to_f64() / 60.0 + values[2].to_f64() / 3600.0
+        }
+        _ => return None,
+    };
+    if !degrees.is_finite() {
+        return None;
+    }
+
+    let negative = ascii(exif, ref_tag).and_then(|value| value.first()) == Some(&negative_ref);
+    Some(if negative { -degrees } else { degrees })
+}
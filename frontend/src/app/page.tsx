@@ -300,23 +300,14 @@ export default function KGAnnotationApp() {
           headers: { 'Content-Type': file.type },
         });
 
-        // 3. 画像の情報を取得 (width, height)
-        const image = new Image();
-        image.src = URL.createObjectURL(file);
-        await new Promise(resolve => { image.onload = resolve; });
-
-        // 4. アップロード完了をバックエンドに通知してDBに保存
+        // 3. アップロード完了をバックエンドに通知してDBに保存（幅・高さなどはバックエンドが画像から読み取る）
         const registeredImage = await registerImage({
           s3_key: s3_key,
           original_filename: file.name,
-          file_size: file.size,
-          width: image.naturalWidth,
-          height: image.naturalHeight,
-          format: file.type,
         });
         console.log(`✅ Image registered in DB: ${registeredImage.id}`);
 
-        // 5. アノテーションデータを保存
+        // 4. アノテーションデータを保存
         const imageAnnotations = annotations[i] || [];
         for (const annotation of imageAnnotations) {
           const annotationData = {
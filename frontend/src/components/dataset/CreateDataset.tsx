@@ -10,7 +10,7 @@ interface CreateDatasetProps {
 export function CreateDataset({ selectedImages, onSuccess, onError }: CreateDatasetProps) {
   const [name, setName] = useState("");
   const [format, setFormat] = useState<DatasetFormat>(DatasetFormat.Yolo);
-  const [filterType, setFilterType] = useState<"detection" | "classification">("detection");
+  const [filterType, setFilterType] = useState<"any" | "all">("any");
   const [selectedLabels, setSelectedLabels] = useState<string[]>([]);
   const [availableLabels, setAvailableLabels] = useState<string[]>([]);
   const [isLoading, setIsLoading] = useState(false);
@@ -101,14 +101,14 @@ export function CreateDataset({ selectedImages, onSuccess, onError }: CreateData
 
         <div>
           <label className="block text-sm font-medium text-gray-700">
-            フィルタータイプ
+            ラベルの一致条件
             <select
               value={filterType}
-              onChange={(e) => setFilterType(e.target.value as "detection" | "classification")}
+              onChange={(e) => setFilterType(e.target.value as "any" | "all")}
               className="mt-1 block w-full rounded-md border-gray-300 shadow-sm focus:border-indigo-500 focus:ring-indigo-500"
             >
-              <option value="detection">物体検出ラベル</option>
-              <option value="classification">分類ラベル</option>
+              <option value="any">いずれかのラベルを含む画像</option>
+              <option value="all">すべてのラベルを含む画像</option>
             </select>
           </label>
         </div>
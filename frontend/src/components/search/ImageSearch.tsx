@@ -1,36 +1,50 @@
 "use client";
 
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import { Search, Loader2 } from "lucide-react";
 import { Button } from "@/components/ui/button";
-import { searchImages } from "@/lib/api";
+import { fetchImage, searchImages } from "@/lib/api";
 import Image from 'next/image';
 
 interface SearchResult {
   id: string;
   similarity: number;  // 必須フィールドに変更
-  imageUrl: string;
+  // サムネイルの Blob URL（取得に失敗した場合は null）
+  imageUrl: string | null;
 }
 
-// Next.jsの設定でAPIのドメインを取得
-const API_BASE_URL = process.env.NEXT_PUBLIC_API_URL || 'http://localhost:3002';
-
 export function ImageSearch() {
   const [query, setQuery] = useState("");
   const [isSearching, setIsSearching] = useState(false);
   const [results, setResults] = useState<SearchResult[]>([]);
 
+  // 検索結果が入れ替わったら前のサムネイルの Blob URL を解放する
+  useEffect(() => {
+    return () => {
+      results.forEach((result) => {
+        if (result.imageUrl) URL.revokeObjectURL(result.imageUrl);
+      });
+    };
+  }, [results]);
+
   const handleSearch = async () => {
     if (!query.trim()) return;
 
     setIsSearching(true);
     try {
       const searchResults = await searchImages(query);  // searchImagesの戻り値の型が変更される
-      const results: SearchResult[] = searchResults.map(result => ({
-        id: result.id,
-        similarity: result.similarity,
-        imageUrl: `${API_BASE_URL}/api/images/${result.id}`
-      }));
+      // <img> からは認証ヘッダーを送れないため、サムネイルを取得して Blob URL で表示する
+      const results: SearchResult[] = await Promise.all(
+        searchResults.map(async (result) => {
+          const imageUrl = await fetchImage(result.id, 'thumb')
+            .then(({ blob }) => URL.createObjectURL(blob))
+            .catch((error) => {
+              console.error("サムネイル取得エラー:", error);
+              return null;
+            });
+          return { id: result.id, similarity: result.similarity, imageUrl };
+        })
+      );
       setResults(results);
     } catch (error) {
       console.error("検索エラー:", error);
@@ -85,11 +99,17 @@ export function ImageSearch() {
               <div key={result.id} className="border rounded-lg p-2">
                 <div className="aspect-square relative mb-2">
                   {/* img要素を使用してNext.jsのImage制限を回避 */}
-                  <img
-                    src={result.imageUrl}
-                    alt="検索結果"
-                    className="w-full h-full object-cover rounded"
-                  />
+                  {result.imageUrl ? (
+                    <img
+                      src={result.imageUrl}
+                      alt="検索結果"
+                      className="w-full h-full object-cover rounded"
+                    />
+                  ) : (
+                    <div className="w-full h-full flex items-center justify-center bg-gray-100 rounded text-xs text-gray-400">
+                      画像を表示できません
+                    </div>
+                  )}
                 </div>
                 <div className="text-sm">
                   <p className="text-gray-600">
@@ -2,6 +2,37 @@
 const AI_API_BASE_URL = process.env.NEXT_PUBLIC_AI_API_URL || 'http://localhost:8001';
 const BACKEND_API_BASE_URL = process.env.NEXT_PUBLIC_API_URL || 'http://localhost:3002';
 
+// バックエンドの認証トークン（JWT）。ログイン後に setAuthToken で設定し、localStorage に保存する
+const AUTH_TOKEN_STORAGE_KEY = 'kg-annotation-auth-token';
+let authToken: string | null = null;
+
+export function setAuthToken(token: string | null): void {
+  authToken = token;
+  if (typeof window === 'undefined') return;
+  if (token) {
+    window.localStorage.setItem(AUTH_TOKEN_STORAGE_KEY, token);
+  } else {
+    window.localStorage.removeItem(AUTH_TOKEN_STORAGE_KEY);
+  }
+}
+
+export function getAuthToken(): string | null {
+  if (authToken === null && typeof window !== 'undefined') {
+    authToken = window.localStorage.getItem(AUTH_TOKEN_STORAGE_KEY);
+  }
+  return authToken;
+}
+
+// バックエンドAPI呼び出し（Authorization: Bearer を付与する）
+async function backendFetch(path: string, init: RequestInit = {}): Promise<Response> {
+  const headers = new Headers(init.headers);
+  const token = getAuthToken();
+  if (token) {
+    headers.set('Authorization', `Bearer ${token}`);
+  }
+  return fetch(`${BACKEND_API_BASE_URL}${path}`, { ...init, headers });
+}
+
 export interface DetectionResult {
   id: number;
   class_name: string;
@@ -128,11 +159,11 @@ export interface AnnotationListResponse {
 
 // アノテーションAPI関数
 export async function getAnnotations(imageId?: string): Promise<AnnotationListResponse> {
-  const url = imageId 
-    ? `${BACKEND_API_BASE_URL}/images/${imageId}/annotations`
-    : `${BACKEND_API_BASE_URL}/annotations`;
+  const path = imageId 
+    ? `/images/${imageId}/annotations`
+    : `/annotations`;
 
-  const response = await fetch(url);
+  const response = await backendFetch(path);
   
   if (!response.ok) {
     throw new Error(`アノテーション取得に失敗: ${response.status}`);
@@ -142,7 +173,7 @@ export async function getAnnotations(imageId?: string): Promise<AnnotationListRe
 }
 
 export async function createAnnotation(annotation: CreateAnnotationRequest): Promise<AnnotationData> {
-  const response = await fetch(`${BACKEND_API_BASE_URL}/annotations`, {
+  const response = await backendFetch(`/annotations`, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -160,7 +191,7 @@ export async function createAnnotation(annotation: CreateAnnotationRequest): Pro
 }
 
 export async function updateAnnotation(id: string, updates: Partial<CreateAnnotationRequest>): Promise<AnnotationData> {
-  const response = await fetch(`${BACKEND_API_BASE_URL}/annotations/${id}`, {
+  const response = await backendFetch(`/annotations/${id}`, {
     method: 'PUT',
     headers: {
       'Content-Type': 'application/json',
@@ -176,7 +207,7 @@ export async function updateAnnotation(id: string, updates: Partial<CreateAnnota
 }
 
 export async function deleteAnnotation(id: string): Promise<{ message: string }> {
-  const response = await fetch(`${BACKEND_API_BASE_URL}/annotations/${id}`, {
+  const response = await backendFetch(`/annotations/${id}`, {
     method: 'DELETE',
   });
 
@@ -187,29 +218,53 @@ export async function deleteAnnotation(id: string): Promise<{ message: string }>
   return response.json();
 }
 
+// 取り込み時に EXIF から読み取ったメタデータ
+export interface ImageMetadata {
+  // 撮影日時（EXIF にタイムゾーンが無い場合はオフセットなしの現地時刻）
+  taken_at?: string;
+  camera_make?: string;
+  camera_model?: string;
+  gps?: {
+    latitude: number;
+    longitude: number;
+    altitude?: number;
+  };
+  // EXIF の Orientation（1〜8）。width・height はこの向きを反映した大きさ
+  orientation?: number;
+  // 向きを画素に反映して保存したか
+  orientation_applied: boolean;
+}
+
 // 画像アップロード関連の型定義
 export interface ImageUploadResponse {
   id: string;
+  s3_key: string;
   filename: string;
-  original_filename: string;
   file_size: number;
   width: number;
   height: number;
   format: string;
-  classification_label?: string;
+  content_hash: string;
+  metadata: ImageMetadata;
   created_at: string;
-  annotation_count: number;
 }
 
-// 画像アップロード
-export async function uploadImage(imageFile: File): Promise<ImageUploadResponse> {
+// ファイルごとのアップロード結果（image・error のどちらか一方）
+export interface ImageUploadResult {
+  filename: string;
+  image?: ImageUploadResponse;
+  error?: string;
+}
+
+// 画像アップロード（複数可）。JPEG・PNG・WebP・TIFF のみ
+export async function uploadImages(imageFiles: File[]): Promise<ImageUploadResult[]> {
   const formData = new FormData();
-  formData.append('image', imageFile);
+  imageFiles.forEach((imageFile) => formData.append('image', imageFile));
 
   try {
     console.log('📤 Uploading to:', `${BACKEND_API_BASE_URL}/images`);
     
-    const response = await fetch(`${BACKEND_API_BASE_URL}/images`, {
+    const response = await backendFetch(`/images`, {
       method: 'POST',
       // CORSの設定を追加
       mode: 'cors',
@@ -222,13 +277,23 @@ export async function uploadImage(imageFile: File): Promise<ImageUploadResponse>
       throw new Error(`画像アップロードに失敗: ${response.status} - ${errorText}`);
     }
 
-    return response.json();
+    const data = await response.json();
+    return data.results;
   } catch (error) {
     console.error('❌ Upload error:', error);
     throw error;
   }
 }
 
+// 画像アップロード
+export async function uploadImage(imageFile: File): Promise<ImageUploadResponse> {
+  const [result] = await uploadImages([imageFile]);
+  if (!result?.image) {
+    throw new Error(`画像アップロードに失敗: ${result?.error}`);
+  }
+  return result.image;
+}
+
 // 検索関数
 interface SearchResult {
   id: string;
@@ -236,7 +301,7 @@ interface SearchResult {
 }
 
 export async function searchImages(query: string): Promise<SearchResult[]> {
-  const response = await fetch(`${BACKEND_API_BASE_URL}/images/search`, {
+  const response = await backendFetch(`/images/search`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({ query }),
@@ -249,8 +314,135 @@ export async function searchImages(query: string): Promise<SearchResult[]> {
   return response.json();
 }
 
+// 画像一覧の型定義
+export interface ImageSummary {
+  id: string;
+  user_id: string;
+  filename: string;
+  original_filename: string;
+  s3_bucket: string;
+  s3_key: string;
+  file_size: number;
+  width: number;
+  height: number;
+  format: string;
+  classification_label?: string;
+  created_at: string;
+  deleted_at: string | null;
+  // EXIF 対応前に登録された画像は null
+  metadata: ImageMetadata | null;
+}
+
+export interface ListImagesParams {
+  // 前のページの next_cursor（sort・order は前のページと同じにする）
+  cursor?: string;
+  limit?: number;
+  user_id?: string;
+  created_after?: string;
+  created_before?: string;
+  format?: string;
+  classification_label?: string;
+  dataset_id?: string;
+  // true: アノテーションあり、false: アノテーションなし
+  annotated?: boolean;
+  label?: string;
+  // true: 削除済み（復元できる）画像のみ
+  deleted?: boolean;
+  sort?: "created_at" | "filename" | "file_size";
+  order?: "asc" | "desc";
+}
+
+export interface ImageListResponse {
+  images: ImageSummary[];
+  next_cursor: string | null;
+}
+
+// 画像一覧（カーソルページング）
+export async function listImages(params: ListImagesParams = {}): Promise<ImageListResponse> {
+  const query = new URLSearchParams();
+  Object.entries(params).forEach(([key, value]) => {
+    if (value !== undefined) {
+      query.append(key, String(value));
+    }
+  });
+
+  const response = await backendFetch(`/images?${query.toString()}`);
+
+  if (!response.ok) {
+    throw new Error(`画像一覧の取得に失敗: ${response.status}`);
+  }
+
+  return response.json();
+}
+
+// 画像の大きさ。thumb（長辺256px）・preview（長辺1600px）は表示用のリサイズ版で、
+// アノテーションの座標は常に元画像（width・height）の座標系
+export type ImageSize = 'original' | 'thumb' | 'preview';
+
+export interface FetchedImage {
+  blob: Blob;
+  // 元画像の大きさ（リサイズ版上の座標を元画像の座標に換算するときに使う）
+  // IMAGE_DELIVERY=redirect の場合はリダイレクト先のレスポンスに含まれないため null（一覧の width・height を使う）
+  width: number | null;
+  height: number | null;
+}
+
+// 画像の取得。<img> は Authorization ヘッダーを送れないため、表示には Blob の URL（URL.createObjectURL）を使う
+// IMAGE_DELIVERY=redirect の場合は署名付きURLへのリダイレクトをたどって取得する
+export async function fetchImage(imageId: string, size: ImageSize = 'original'): Promise<FetchedImage> {
+  const response = await backendFetch(`/images/${imageId}?size=${size}`);
+
+  if (!response.ok) {
+    throw new Error(`画像の取得に失敗: ${response.status}`);
+  }
+
+  const width = response.headers.get('x-image-width');
+  const height = response.headers.get('x-image-height');
+  return {
+    blob: await response.blob(),
+    width: width === null ? null : Number(width),
+    height: height === null ? null : Number(height),
+  };
+}
+
+// 画像の削除オプション
+// force: ロック・公開済みのデータセットに含まれる画像も削除する
+// permanent: 復元できないよう、ストレージのオブジェクトとともに完全に削除する
+export interface DeleteImageOptions {
+  force?: boolean;
+  permanent?: boolean;
+}
+
+// 画像の削除（複数可）
+export async function deleteImages(imageIds: string[], options: DeleteImageOptions = {}): Promise<void> {
+  const response = await backendFetch(`/images`, {
+    method: 'DELETE',
+    headers: { 'Content-Type': 'application/json' },
+    body: JSON.stringify({ image_ids: imageIds, ...options }),
+  });
+
+  if (!response.ok) {
+    const errorText = await response.text();
+    throw new Error(`画像の削除に失敗: ${response.status} - ${errorText}`);
+  }
+}
+
+// 削除した画像の復元
+export async function restoreImage(imageId: string): Promise<ImageSummary> {
+  const response = await backendFetch(`/images/${imageId}/restore`, {
+    method: 'POST',
+  });
+
+  if (!response.ok) {
+    throw new Error(`画像の復元に失敗: ${response.status}`);
+  }
+
+  return response.json();
+}
+
 export enum DatasetFormat {
   Yolo = 'yolo',
+  YoloSeg = 'yolo-seg',
   Coco = 'coco',
   Voc = 'voc',
 }
@@ -260,15 +452,18 @@ export interface ExportDatasetRequest {
   name: string;
   format: DatasetFormat;
   filter: {
-    type: "detection" | "classification";
+    // any: いずれかのラベルを持つ画像、all: すべてのラベルを持つ画像
+    type: "any" | "all";
     labels: string[];
   };
+  // ラベルからクラスID（0始まり）への対応。省略時は filter.labels の順、filter.labels も空ならラベル管理（/api/labels）のクラスID
+  class_ids?: Record<string, number>;
   image_ids?: string[]; 
 }
 
 // データセットエクスポートAPI関数
 export async function exportDataset(request: ExportDatasetRequest): Promise<Blob> {
-  const response = await fetch(`${BACKEND_API_BASE_URL}/export`, {
+  const response = await backendFetch(`/export`, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
@@ -286,7 +481,7 @@ export async function exportDataset(request: ExportDatasetRequest): Promise<Blob
 
 // 利用可能なアノテーションラベル一覧を取得する関数
 export async function getAvailableLabels(): Promise<string[]> {
-  const response = await fetch(`${BACKEND_API_BASE_URL}/annotations/labels`);
+  const response = await backendFetch(`/annotations/labels`);
   
   if (!response.ok) {
     throw new Error(`ラベル一覧の取得に失敗: ${response.status}`);
@@ -304,7 +499,7 @@ export interface PresignedUrlResponse {
 
 // 事前署名URLを取得する関数
 export async function getPresignedUrl(filename: string): Promise<PresignedUrlResponse> {
-  const response = await fetch(`${BACKEND_API_BASE_URL}/images/presigned-url`, {
+  const response = await backendFetch(`/images/presigned-url`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify({ filename }),
@@ -318,27 +513,33 @@ export async function getPresignedUrl(filename: string): Promise<PresignedUrlRes
 }
 
 export interface RegisterImageRequest {
+  // getPresignedUrl で発行された s3_key
   s3_key: string;
   original_filename: string;
-  file_size: number;
-  width: number;
-  height: number;
-  format: string;
 }
 
+// file_size・width・height・format はアップロードされた画像からバックエンドが読み取った値
 export interface RegisterImageResponse {
   id: string;
+  file_size: number;
+  width: number;
+  height: number;
+  format: string;
+  // 画像本体の SHA-256
+  content_hash: string;
+  metadata: ImageMetadata;
 }
 
 export async function registerImage(imageData: RegisterImageRequest): Promise<RegisterImageResponse> {
-  const response = await fetch(`${BACKEND_API_BASE_URL}/images/register`, {
+  const response = await backendFetch(`/images/register`, {
     method: 'POST',
     headers: { 'Content-Type': 'application/json' },
     body: JSON.stringify(imageData),
   });
 
   if (!response.ok) {
-    throw new Error(`画像の登録に失敗: ${response.status}`);
+    const errorText = await response.text();
+    throw new Error(`画像の登録に失敗: ${response.status} - ${errorText}`);
   }
 
   return response.json();